@@ -0,0 +1,101 @@
+use ark_bls12_381::Fr;
+use ark_std::test_rng;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use miniplonk::{
+    setup, Circuit, CircuitBuilder, InputAssignment, InputConfig, Prover, VerificationKey, Verifier,
+};
+
+/// Number of cells of the benchmarked circuits, from 2^4 to 2^12.
+const LOG_SIZES: [u32; 5] = [4, 6, 8, 10, 12];
+
+/// Build a chain of `n` gates alternating addition and multiplication.
+/// out_0 = pub_0 + priv_0, out_i = out_(i-1) * pub_0 or out_(i-1) + priv_0
+fn bench_circuit(n: usize) -> Circuit {
+    let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+    let (pb_refs, prv_refs) = builder.get_input_refs();
+    let mut out = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
+    for i in 1..n {
+        out = if i % 2 == 1 {
+            builder.add_multiplication(out, pb_refs[0]).unwrap()
+        } else {
+            builder.add_addition(out, prv_refs[0]).unwrap()
+        };
+    }
+
+    builder.build().unwrap()
+}
+
+/// Prover and verification key of a circuit with at most 2^log_size cells.
+fn bench_prover(log_size: u32) -> (Prover<Fr>, VerificationKey, Vec<Fr>) {
+    // 3 cells per gate and 2 input cells
+    let n_gates = ((1 << log_size) - 2) / 3;
+    let circ = bench_circuit(n_gates);
+    let public_inputs = vec![Fr::from(3)];
+    let domain_size = circ.n_cells().next_power_of_two();
+
+    let mut rng = test_rng();
+    let pp = setup(&circ, &public_inputs, &mut rng, 3 * domain_size).unwrap();
+    let inputs = InputAssignment::with_values(&circ, &public_inputs, &[Fr::from(7)]).unwrap();
+    let vk = pp.verification_key().clone();
+    let prover = Prover::<Fr>::new(circ, pp, inputs).unwrap();
+
+    (prover, vk, public_inputs)
+}
+
+fn bench_calculate_witness(c: &mut Criterion) {
+    let mut group = c.benchmark_group("calculate_witness");
+    for log_size in LOG_SIZES {
+        let (mut prover, _, _) = bench_prover(log_size);
+        group.bench_with_input(BenchmarkId::from_parameter(1 << log_size), &(), |b, _| {
+            b.iter(|| prover.calculate_witness().unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_compute_trace_polynomial(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compute_trace_polynomial");
+    for log_size in LOG_SIZES {
+        let (mut prover, _, _) = bench_prover(log_size);
+        prover.calculate_witness().unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(1 << log_size), &(), |b, _| {
+            b.iter(|| prover.compute_trace_polynomial().unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Run with `--features parallel` to measure the speedup of committing on multiple threads.
+fn bench_prove(c: &mut Criterion) {
+    let mut group = c.benchmark_group("prove");
+    group.sample_size(10);
+    for log_size in LOG_SIZES {
+        let (mut prover, _, _) = bench_prover(log_size);
+        group.bench_with_input(BenchmarkId::from_parameter(1 << log_size), &(), |b, _| {
+            b.iter(|| prover.prove().unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_verify(c: &mut Criterion) {
+    let mut group = c.benchmark_group("verify");
+    for log_size in LOG_SIZES {
+        let (mut prover, vk, public_inputs) = bench_prover(log_size);
+        let proof = prover.prove().unwrap();
+        let mut verifier = Verifier::<Fr>::from_vk(vk, public_inputs);
+        group.bench_with_input(BenchmarkId::from_parameter(1 << log_size), &(), |b, _| {
+            b.iter(|| assert!(verifier.verify(proof.clone())))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_calculate_witness,
+    bench_compute_trace_polynomial,
+    bench_prove,
+    bench_verify
+);
+criterion_main!(benches);
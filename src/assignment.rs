@@ -0,0 +1,210 @@
+use anyhow::{anyhow, Result};
+use ark_ff::PrimeField;
+use std::collections::HashMap;
+
+use crate::circuit::{Circuit, InputConfig};
+
+/// Public input values whose number is checked against the input config when created,
+/// instead of when the witness is computed.
+/// Accepted wherever public inputs are given, e.g. `setup`, `InputAssignment::with_values`
+/// and `Verifier::new`.
+///
+/// Named apart from `PublicInputs`, which holds the refs to the public input cells.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicInputValues<F>(Vec<F>);
+
+impl<F> TryFrom<(&InputConfig, Vec<F>)> for PublicInputValues<F> {
+    type Error = anyhow::Error;
+
+    fn try_from((config, values): (&InputConfig, Vec<F>)) -> Result<Self> {
+        if values.len() != config.n_pub() {
+            return Err(anyhow!(
+                "Expected {} public inputs, got {}.",
+                config.n_pub(),
+                values.len()
+            ));
+        }
+        Ok(Self(values))
+    }
+}
+
+impl<F> PublicInputValues<F> {
+    /// Returns the values in order of the public inputs.
+    pub fn values(&self) -> &[F] {
+        &self.0
+    }
+}
+
+impl<F> AsRef<[F]> for PublicInputValues<F> {
+    fn as_ref(&self) -> &[F] {
+        &self.0
+    }
+}
+
+impl<F> From<PublicInputValues<F>> for Vec<F> {
+    fn from(values: PublicInputValues<F>) -> Self {
+        values.0
+    }
+}
+
+/// Values assigned to the inputs of a circuit.
+///
+/// Inputs are set by their index among public or private inputs,
+/// the same index used by `CircuitBuilder::get_input_refs`.
+/// Callers don't need to know in which cell each input is placed.
+#[derive(Clone, Debug)]
+pub struct InputAssignment<F> {
+    public: Vec<Option<F>>,
+    private: Vec<Option<F>>,
+}
+
+impl<F: Copy> InputAssignment<F> {
+    /// Create empty assignment for the inputs of `circuit`.
+    pub fn new(circuit: &Circuit) -> Self {
+        Self {
+            public: vec![None; circuit.input_config.n_pub()],
+            private: vec![None; circuit.input_config.n_priv()],
+        }
+    }
+
+    /// Create assignment from all public and private input values in order.
+    pub fn with_values(circuit: &Circuit, public: impl AsRef<[F]>, private: &[F]) -> Result<Self> {
+        let public = public.as_ref();
+        let mut assignment = Self::new(circuit);
+        if public.len() != assignment.public.len() {
+            return Err(anyhow!(
+                "Expected {} public inputs, got {}.",
+                assignment.public.len(),
+                public.len()
+            ));
+        }
+        if private.len() != assignment.private.len() {
+            return Err(anyhow!(
+                "Expected {} private inputs, got {}.",
+                assignment.private.len(),
+                private.len()
+            ));
+        }
+
+        assignment.public = public.iter().copied().map(Some).collect();
+        assignment.private = private.iter().copied().map(Some).collect();
+        Ok(assignment)
+    }
+
+    /// Create assignment from public input values in order and private input values
+    /// by the names given with `CircuitBuilder::name_input`.
+    /// Returns error if a private input has no name or no value, or a name is not
+    /// of a private input.
+    pub fn with_labels(
+        circuit: &Circuit,
+        public: impl AsRef<[F]>,
+        private: &HashMap<&str, F>,
+    ) -> Result<Self> {
+        let n_pub = circuit.input_config.n_pub();
+        for name in private.keys() {
+            match circuit.input_number(name) {
+                None => return Err(anyhow!("Input {} does not exist.", name)),
+                Some(number) if number < n_pub => return Err(anyhow!("Input {} is public.", name)),
+                _ => {}
+            }
+        }
+
+        let mut names = vec![None; circuit.input_config.n_priv()];
+        for (name, number) in &circuit.input_names {
+            if *number >= n_pub {
+                names[number - n_pub] = Some(name.as_str());
+            }
+        }
+        let private = names
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let name = name.ok_or(anyhow!("Private input {} has no name.", i))?;
+                private
+                    .get(name)
+                    .copied()
+                    .ok_or(anyhow!("Private input {} is not assigned.", name))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Self::with_values(circuit, public, &private)
+    }
+
+    /// Assign `value` to the `index`-th public input.
+    pub fn set_public(&mut self, index: usize, value: F) -> Result<()> {
+        let input = self
+            .public
+            .get_mut(index)
+            .ok_or(anyhow!("Public input {} does not exist.", index))?;
+        *input = Some(value);
+        Ok(())
+    }
+
+    /// Assign `value` to the `index`-th private input.
+    pub fn set_private(&mut self, index: usize, value: F) -> Result<()> {
+        let input = self
+            .private
+            .get_mut(index)
+            .ok_or(anyhow!("Private input {} does not exist.", index))?;
+        *input = Some(value);
+        Ok(())
+    }
+
+    /// Returns the number of public inputs.
+    pub(crate) fn n_pub(&self) -> usize {
+        self.public.len()
+    }
+
+    /// Returns the number of private inputs.
+    pub(crate) fn n_priv(&self) -> usize {
+        self.private.len()
+    }
+
+    /// Returns all input values ordered by input number, public inputs first.
+    /// Input `i` of the returned vec is placed at cell `n_cells - (i + 1)`.
+    pub(crate) fn ordered_values(&self) -> Result<Vec<F>> {
+        let public = self
+            .public
+            .iter()
+            .enumerate()
+            .map(|(i, v)| v.ok_or(anyhow!("Public input {} is not assigned.", i)));
+        let private = self
+            .private
+            .iter()
+            .enumerate()
+            .map(|(i, v)| v.ok_or(anyhow!("Private input {} is not assigned.", i)));
+
+        public.chain(private).collect()
+    }
+}
+
+impl<F: PrimeField> InputAssignment<F> {
+    /// Create assignment from the canonical integer representations of input values,
+    /// which can be as large as the modulus minus one.
+    /// Returns error if any value is not smaller than the modulus.
+    pub fn with_bigints(
+        circuit: &Circuit,
+        public: &[F::BigInt],
+        private: &[F::BigInt],
+    ) -> Result<Self> {
+        let to_field = |kind: &str, values: &[F::BigInt]| {
+            values
+                .iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    F::from_bigint(*value).ok_or(anyhow!(
+                        "{} input {} is not smaller than the modulus.",
+                        kind,
+                        i
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+
+        Self::with_values(
+            circuit,
+            &to_field("Public", public)?,
+            &to_field("Private", private)?,
+        )
+    }
+}
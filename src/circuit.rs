@@ -1,5 +1,17 @@
-use anyhow::{anyhow, Result};
-use std::collections::HashSet;
+use anyhow::{anyhow, Context, Result};
+use ark_ff::{FftField, Field, PrimeField};
+use ark_poly::{univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain};
+use num_bigint::BigUint;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use thiserror::Error;
+
+use crate::{
+    common::vanishing_over_cells,
+    gate::{GateLayout, GateOperand},
+    prover::DEFAULT_BLINDING_DEGREE,
+    r1cs::{Constraint, Variable},
+};
 
 /*
 - Define private/public inputs with input configuration.
@@ -13,7 +25,112 @@ use std::collections::HashSet;
 // In order to represent both intermediate cells and input cells,
 // For input cells, id: total cell - input number
 // For intermediate cells, id:
-type Id = usize;
+pub(crate) type Id = usize;
+
+/// Id of a cell, which is also the exponent of its point 𝜔^id in the evaluation domain.
+/// Gate cells come first, 3 per row, then constants, and inputs in reverse order at the end.
+///
+/// Cell ids, row indices and input indices are all numbers, their types keep them apart:
+///
+/// ```compile_fail
+/// use miniplonk::{CellId, RowIndex};
+///
+/// let row: RowIndex = CellId(4);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct CellId(pub usize);
+
+/// Index of a row of the trace, holding the lhs, rhs and out cells of a gate.
+///
+/// ```compile_fail
+/// use miniplonk::{CellId, InputIndex, RowIndex};
+///
+/// let cell: CellId = RowIndex(1).cells()[0];
+/// let input: InputIndex = cell;
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct RowIndex(pub usize);
+
+/// Number of an input among all inputs, public inputs first.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct InputIndex(pub usize);
+
+impl CellId {
+    /// Returns the row of a gate cell.
+    pub fn row(self) -> RowIndex {
+        RowIndex(self.0 / 3)
+    }
+
+    /// Returns the input placed at an input cell of a circuit of `n_cells` cells.
+    pub fn input_index(self, n_cells: usize) -> InputIndex {
+        InputIndex(n_cells - (self.0 + 1))
+    }
+}
+
+impl RowIndex {
+    /// Returns the lhs, rhs and out cells of the row.
+    pub fn cells(self) -> [CellId; 3] {
+        let id = self.0 * 3;
+        [CellId(id), CellId(id + 1), CellId(id + 2)]
+    }
+}
+
+impl InputIndex {
+    /// Returns the cell of the input in a circuit of `n_cells` cells.
+    /// Input i is placed at cell `n_cells - (i + 1)`.
+    pub fn cell(self, n_cells: usize) -> CellId {
+        CellId(n_cells - (self.0 + 1))
+    }
+}
+
+macro_rules! impl_index {
+    ($name:ident) => {
+        impl From<usize> for $name {
+            fn from(index: usize) -> Self {
+                Self(index)
+            }
+        }
+
+        impl From<$name> for usize {
+            fn from(index: $name) -> Self {
+                index.0
+            }
+        }
+    };
+}
+
+impl_index!(CellId);
+impl_index!(RowIndex);
+impl_index!(InputIndex);
+
+/// Errors on building a circuit.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Error)]
+pub enum CircuitBuildError {
+    #[error("Input {0} does not exist.")]
+    InvalidInputRef(usize),
+    #[error("Wire {0} does not exist.")]
+    InvalidWireRef(usize),
+    #[error("Constant {0} does not exist.")]
+    InvalidConstantRef(usize),
+    #[error("Circuit has no gates.")]
+    NoGates,
+    #[error("Wiring {0:?} to {1:?} joins cells fixed to different values.")]
+    ConflictingConstraint(Cellref, Cellref),
+    #[error("Circuit has {0} selectors but {1} rows.")]
+    SelectorCountMismatch(usize, usize),
+}
+
+/// Read-only view of a gate constraint lhs + rhs = out or lhs * rhs = out.
+/// Gates of a wide sum share cells, so a gate may span two rows.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GateView {
+    /// Row of the lhs cell.
+    pub row: usize,
+    pub op: Op,
+    pub lhs: usize,
+    pub rhs: usize,
+    pub out: usize,
+}
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Op {
@@ -21,7 +138,7 @@ pub enum Op {
     Mul = 0,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct InputConfig {
     n_pub: usize,
     n_priv: usize,
@@ -49,15 +166,66 @@ impl InputConfig {
     }
 }
 
+/// Position of the output cell of a circuit.
+/// The output is T(𝜔^cell) over the evaluation domain of `domain_size`,
+/// which is what `Prover::prove_statement` opens.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct OutputBinding {
+    pub cell: CellId,
+    pub domain_size: usize,
+}
+
+/// Value of a constant cell.
+/// Circuits don't fix a field, so the value is kept as a canonical integer
+/// and reduced into the field of the witness when it's used.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct Constant(BigUint);
+
+impl Constant {
+    /// Constant holding the canonical integer of `value`.
+    pub(crate) fn from_field<F: PrimeField>(value: F) -> Self {
+        Self(value.into())
+    }
+
+    /// Returns the constant as an element of `F`, reduced modulo its characteristic.
+    pub(crate) fn to_field<F: Field>(&self) -> F {
+        let base = F::from(u64::MAX) + F::one();
+        self.0
+            .iter_u64_digits()
+            .rev()
+            .fold(F::zero(), |acc, digit| acc * base + F::from(digit))
+    }
+}
+
+impl From<u64> for Constant {
+    fn from(value: u64) -> Self {
+        Self(value.into())
+    }
+}
+
+impl fmt::Display for Constant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// Circuit struct
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Circuit {
     pub(crate) input_config: InputConfig,
     pub(crate) selectors: Vec<Op>,
-    copy_constraints: Vec<Vec<Id>>,
+    /// Id of the first cell and operation of each gate constraint.
+    /// Gate at id constrains cells id (lhs), id + 1 (rhs) and id + 2 (out).
+    pub(crate) gates: Vec<(Id, Op)>,
+    /// Selector on each gate cell, true on the first cell of addition gates.
+    pub(crate) selector_evals: Vec<bool>,
+    pub(crate) copy_constraints: Vec<Vec<Id>>,
+    /// Values of constant cells, placed right after the gate cells.
+    pub(crate) constants: Vec<Constant>,
 
     /// Total number of cells including.
     /// gate constraints cells: lhs, rhs, out.
+    /// constant cells.
     /// input cells: public inputs, private inputs.
     n_cells: usize,
 
@@ -67,6 +235,9 @@ pub struct Circuit {
     /// The last cell id of computation trace table.
     /// Circuit allows single output.
     output: Id,
+
+    /// Input number of each input named by `CircuitBuilder::name_input`.
+    pub(crate) input_names: HashMap<String, usize>,
 }
 
 impl Circuit {
@@ -85,36 +256,874 @@ impl Circuit {
         self.n_rows
     }
 
+    /// Returns the ids of the input cells, which come last.
+    /// Input i is placed at cell `n_cells - (i + 1)`, so public inputs are at the end.
+    pub fn input_cell_ids(&self) -> impl ExactSizeIterator<Item = CellId> {
+        (self.n_cells - self.n_inputs()..self.n_cells).map(CellId)
+    }
+
+    /// Returns the first cell of each padding row.
+    /// The domain is padded to a power of two after the input cells, and padding cells are
+    /// zero in the trace and the selector. Complete rows of them satisfy the gate identity
+    /// with any selector since 0 + 0 = 0 ⋅ 0 = 0, though it's only checked on the gates.
+    /// Rows are aligned to multiples of 3 like the gate rows, so padding cells before the
+    /// first aligned cell and after the last complete row are in no row. The gate identity
+    /// isn't checked on them, so they need no handling.
+    pub fn padding_rows(&self) -> impl Iterator<Item = Id> {
+        let domain_size = self.n_cells.checked_next_power_of_two().unwrap();
+        (self.n_cells.next_multiple_of(3)..domain_size.saturating_sub(2)).step_by(3)
+    }
+
+    /// Returns the numbers of public and private inputs.
+    pub fn input_config(&self) -> InputConfig {
+        self.input_config
+    }
+
+    /// Returns a copy of the circuit with inputs split into public and private by `new_config`.
+    /// Inputs keep their numbers and cells, so only the boundary between the public inputs
+    /// and the private ones moves, e.g. the last public input becomes the first private one.
+    /// The public input constraint follows the new number of public inputs.
+    pub fn with_input_visibility(&self, new_config: InputConfig) -> Result<Circuit> {
+        if new_config.total_input() != self.input_config.total_input() {
+            return Err(anyhow!(
+                "Expected {} inputs in total, got {}.",
+                self.input_config.total_input(),
+                new_config.total_input()
+            ));
+        }
+
+        let mut circuit = self.clone();
+        circuit.input_config = new_config;
+        Ok(circuit)
+    }
+
+    /// Returns a copy of the circuit with the `fixed` inputs folded in as constant cells,
+    /// e.g. a key or parameter known at preprocessing time.
+    /// The remaining inputs keep their order and are renumbered from 0, public inputs first.
+    /// Gates are kept as they are, so the rows and the output cell don't move.
+    /// Returns error if an input doesn't exist or is fixed twice,
+    /// or the input is wired to a constant of a different value.
+    pub fn specialize<F: PrimeField>(&self, fixed: &[(InputIndex, F)]) -> Result<Circuit> {
+        let n_inputs = self.n_inputs();
+        let mut constants = self.constants.clone();
+        let mut fixed_constants = HashMap::new();
+        for (input, value) in fixed {
+            if input.0 >= n_inputs {
+                return Err(anyhow!("Input {} does not exist.", input.0));
+            }
+            let value = Constant::from_field(*value);
+            let index = match constants.iter().position(|c| *c == value) {
+                Some(index) => index,
+                None => {
+                    constants.push(value);
+                    constants.len() - 1
+                }
+            };
+            if fixed_constants.insert(input.0, index).is_some() {
+                return Err(anyhow!("Input {} is fixed more than once.", input.0));
+            }
+        }
+
+        let kept = (0..n_inputs)
+            .filter(|i| !fixed_constants.contains_key(i))
+            .collect::<Vec<_>>();
+        let n_pub = kept
+            .iter()
+            .filter(|i| **i < self.input_config.n_pub())
+            .count();
+        let input_config = InputConfig::new(n_pub, kept.len() - n_pub);
+
+        let gate_cells = self.n_rows * 3;
+        let n_cells = gate_cells + constants.len() + kept.len();
+        let new_cell = |id: Id| {
+            if id < gate_cells + self.constants.len() {
+                return id;
+            }
+            let input = CellId(id).input_index(self.n_cells).0;
+            match fixed_constants.get(&input) {
+                Some(index) => gate_cells + index,
+                None => {
+                    let number = kept.binary_search(&input).unwrap();
+                    InputIndex(number).cell(n_cells).0
+                }
+            }
+        };
+
+        // an input fixed to an existing constant joins the class of the constant
+        let mut class_of: HashMap<Id, usize> = HashMap::new();
+        let mut classes: Vec<Vec<Id>> = vec![];
+        let new_constant_cells = (gate_cells + self.constants.len()..gate_cells + constants.len())
+            .map(|id| vec![id])
+            .collect::<Vec<_>>();
+        for class in self
+            .copy_constraints
+            .iter()
+            .chain(new_constant_cells.iter())
+        {
+            let mut cells = class.iter().map(|id| new_cell(*id)).collect::<Vec<_>>();
+            cells.sort();
+            cells.dedup();
+            match cells.iter().find_map(|id| class_of.get(id).copied()) {
+                Some(i) => {
+                    for id in cells {
+                        if !classes[i].contains(&id) {
+                            class_of.insert(id, i);
+                            classes[i].push(id);
+                        }
+                    }
+                }
+                None => {
+                    for id in &cells {
+                        class_of.insert(*id, classes.len());
+                    }
+                    classes.push(cells);
+                }
+            }
+        }
+
+        let constant_cells = gate_cells..gate_cells + constants.len();
+        for class in &classes {
+            let values = class
+                .iter()
+                .filter(|id| constant_cells.contains(id))
+                .map(|id| &constants[id - gate_cells])
+                .collect::<Vec<_>>();
+            if values.len() > 1 {
+                return Err(anyhow!(
+                    "Fixed inputs wire constant {} to constant {}.",
+                    values[0],
+                    values[1]
+                ));
+            }
+        }
+
+        // same order as the builder, so the permutation doesn't depend on the fixed order
+        for class in classes.iter_mut() {
+            class.sort();
+        }
+        classes.sort_by_key(|class| class[0]);
+
+        let input_names = self
+            .input_names
+            .iter()
+            .filter_map(|(name, number)| {
+                let number = kept.binary_search(number).ok()?;
+                Some((name.clone(), number))
+            })
+            .collect();
+
+        let circuit = Circuit {
+            input_config,
+            constants,
+            copy_constraints: classes,
+            n_cells,
+            input_names,
+            ..self.clone()
+        };
+        circuit.validate_permutation()?;
+        Ok(circuit)
+    }
+
+    /// Returns the input number of the input named `name`, public inputs first.
+    pub fn input_number(&self, name: &str) -> Option<usize> {
+        self.input_names.get(name).copied()
+    }
+
     /// Returns the id of output cell.
-    pub fn output_id(&self) -> Id {
-        self.output
+    pub fn output_id(&self) -> CellId {
+        CellId(self.output)
+    }
+
+    /// Returns the out cells of the gates whose result no other gate uses, in row order.
+    /// These are the results of a circuit computing several values, `output_id` usually
+    /// the last of them.
+    pub fn outputs(&self) -> Vec<CellId> {
+        let class = self.cell_classes();
+        let mut used = vec![false; self.n_cells];
+        for gate in self.gates() {
+            used[class[gate.lhs]] = true;
+            used[class[gate.rhs]] = true;
+        }
+
+        let mut outputs = self
+            .gates()
+            .filter(|gate| !used[class[gate.out]])
+            .map(|gate| CellId(gate.out))
+            .collect::<Vec<_>>();
+        outputs.sort();
+        outputs
+    }
+
+    /// Returns the output cell, so the output can be bound to an input of another circuit.
+    pub fn output_binding(&self) -> OutputBinding {
+        OutputBinding {
+            cell: CellId(self.output),
+            domain_size: self.n_cells.next_power_of_two(),
+        }
+    }
+
+    /// Check the internal consistency of the circuit.
+    /// Every row has exactly one selector.
+    pub fn validate_invariants(&self) -> Result<(), CircuitBuildError> {
+        if self.selectors.len() != self.n_rows {
+            return Err(CircuitBuildError::SelectorCountMismatch(
+                self.selectors.len(),
+                self.n_rows,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check the wire permutation, which maps each cell to the next cell of its copy
+    /// constraint class, is a bijection over `0..n_cells`.
+    /// A cell in two classes would be the image of two cells, breaking the permutation argument.
+    pub fn validate_permutation(&self) -> Result<()> {
+        let mut rotation = (0..self.n_cells).collect::<Vec<_>>();
+        for (i, class) in self.copy_constraints.iter().enumerate() {
+            if class.is_empty() {
+                return Err(anyhow!("Copy constraint class {} is empty.", i));
+            }
+            for (j, id) in class.iter().enumerate() {
+                if *id >= self.n_cells {
+                    return Err(anyhow!("Cell {} does not exist.", id));
+                }
+                rotation[*id] = class[(j + 1) % class.len()];
+            }
+        }
+
+        let mut seen = vec![false; self.n_cells];
+        for id in rotation {
+            if std::mem::replace(&mut seen[id], true) {
+                return Err(anyhow!(
+                    "Cell {} appears more than once in the permutation.",
+                    id
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the length of the longest chain of gates the output depends on, i.e. the
+    /// number of rounds to compute the witness if independent gates are evaluated together.
+    /// A cell filled by a gate is at one level past the deeper of the other two cells,
+    /// inputs and constants at 0. Gates fill cells as in witness computation, so the
+    /// rhs solved backward, e.g. an inverse of `add_division`, is leveled from lhs and out.
+    /// Returns error if the output cannot be computed from the inputs.
+    pub fn depth(&self) -> Result<usize> {
+        let class = self.cell_classes();
+
+        let mut cell_gates = vec![vec![]; self.n_cells];
+        for gate in self.gates() {
+            for cell in [gate.lhs, gate.rhs, gate.out] {
+                cell_gates[class[cell]].push(gate);
+            }
+        }
+
+        // classes are visited in order of their level, so each gets its smallest level
+        let mut levels = vec![None; self.n_cells];
+        let mut queue = VecDeque::new();
+        for id in self
+            .input_cell_ids()
+            .map(|cell| cell.0)
+            .chain(self.constant_cells().map(|(id, _)| id))
+        {
+            if levels[class[id]].is_none() {
+                levels[class[id]] = Some(0);
+                queue.push_back(class[id]);
+            }
+        }
+        while let Some(id) = queue.pop_front() {
+            for gate in &cell_gates[id] {
+                let [lhs, rhs, out] = [gate.lhs, gate.rhs, gate.out].map(|cell| class[cell]);
+                let (filled, level) = match (levels[lhs], levels[rhs], levels[out]) {
+                    (Some(lhs), Some(rhs), None) => (out, lhs.max(rhs)),
+                    // rhs = out - lhs or rhs = out / lhs
+                    (Some(lhs), None, Some(out)) => (rhs, lhs.max(out)),
+                    _ => continue,
+                };
+                levels[filled] = Some(level + 1);
+                queue.push_back(filled);
+            }
+        }
+
+        levels[class[self.output]].ok_or(anyhow!("Output cannot be computed from the inputs."))
+    }
+
+    /// Returns the copy constraint class of each cell, named by its first cell.
+    /// Cells which are not wired are their own class.
+    fn cell_classes(&self) -> Vec<Id> {
+        let mut class = (0..self.n_cells).collect::<Vec<_>>();
+        for cells in &self.copy_constraints {
+            for id in cells {
+                class[*id] = cells[0];
+            }
+        }
+        class
+    }
+
+    /// Returns which gates may take part in filling `target`.
+    /// A gate fills its out from lhs and rhs, or its rhs from lhs and out, so the gates
+    /// filling a cell are collected with those filling the other two cells, back to the inputs.
+    fn dependency_cone(&self, target: Id) -> Vec<bool> {
+        let class = self.cell_classes();
+
+        // gates able to fill each class, with the classes they need
+        let mut fillers = vec![vec![]; self.n_cells];
+        for (i, gate) in self.gates().enumerate() {
+            fillers[class[gate.out]].push((i, [class[gate.lhs], class[gate.rhs]]));
+            fillers[class[gate.rhs]].push((i, [class[gate.lhs], class[gate.out]]));
+        }
+
+        let mut in_cone = vec![false; self.gates.len()];
+        let mut visited = vec![false; self.n_cells];
+        visited[class[target]] = true;
+        let mut stack = vec![class[target]];
+        while let Some(id) = stack.pop() {
+            for (gate, needed) in &fillers[id] {
+                in_cone[*gate] = true;
+                for needed in needed {
+                    if !std::mem::replace(&mut visited[*needed], true) {
+                        stack.push(*needed);
+                    }
+                }
+            }
+        }
+        in_cone
+    }
+
+    /// Returns the size of the largest copy constraint class.
+    pub fn max_fan_out(&self) -> usize {
+        self.copy_constraints
+            .iter()
+            .map(|class| class.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of cells wired to at least one other cell.
+    pub fn total_wired_cells(&self) -> usize {
+        self.copy_constraints
+            .iter()
+            .filter(|class| class.len() > 1)
+            .map(|class| class.len())
+            .sum()
     }
 
     /// Very naive way to retrieve set of cell ids share same value(copy constraints).
-    pub fn get_copy_constraints(&self, id: Id) -> Option<&[Id]> {
+    pub fn get_copy_constraints(&self, cell: CellId) -> Option<Vec<CellId>> {
+        self.copy_constraint_class(cell.0)
+            .map(|class| class.iter().copied().map(CellId).collect())
+    }
+
+    /// Returns the copy constraint class holding `id`, if it is wired.
+    pub(crate) fn copy_constraint_class(&self, id: Id) -> Option<&[Id]> {
         self.copy_constraints
             .iter()
             .find(|v| v.contains(&id))
             .map(|v| v.as_slice())
     }
 
+    /// Returns the indices of inputs, public ones first, which are not wired into any gate.
+    /// Their cells are still filled in the witness and bound by the input constraint,
+    /// but they don't affect the output, which is likely a mistake in the circuit.
+    pub fn unused_inputs(&self) -> Vec<usize> {
+        let gate_cells = 0..self.n_rows * 3;
+        (0..self.input_config.total_input())
+            .filter(|i| {
+                let id = InputIndex(*i).cell(self.n_cells).0;
+                !self
+                    .copy_constraint_class(id)
+                    .unwrap_or(&[])
+                    .iter()
+                    .any(|cell| gate_cells.contains(cell))
+            })
+            .collect()
+    }
+
+    /// Returns the number of gate constraints.
+    pub fn num_gates(&self) -> usize {
+        self.gates.len()
+    }
+
+    /// Returns the maximum degree of the polynomials committed while proving the circuit,
+    /// which `setup` needs to support.
+    /// The gate quotient is the largest, (1 - S)⋅T(X)⋅T(𝜔X) divided by the gate vanishing polynomial.
+    /// T is blinded with `DEFAULT_BLINDING_DEGREE` coefficients.
+    pub fn degree_bound(&self) -> usize {
+        self.degree_bound_with_blinding(DEFAULT_BLINDING_DEGREE)
+    }
+
+    /// Returns the maximum degree of the polynomials committed while proving the circuit
+    /// with T and Z blinded with `k` coefficients, see `Prover::set_blinding_degree`.
+    pub fn degree_bound_with_blinding(&self, k: usize) -> usize {
+        let domain_size = self.n_cells.checked_next_power_of_two().unwrap();
+        (3 * (domain_size - 1) + 2 * k)
+            .saturating_sub(self.num_gates())
+            .max(domain_size - 1 + k)
+    }
+
+    /// Returns the operation of every gate if all the gates share the same one.
+    /// In an all-multiplication circuit the selector polynomial is zero.
+    pub fn selector_is_constant(&self) -> Option<Op> {
+        let (_, op) = self.gates.first()?;
+        self.gates
+            .iter()
+            .all(|(_, other)| other == op)
+            .then_some(*op)
+    }
+
     pub fn get_selector(&self, row: usize) -> Option<Op> {
         self.selectors.get(row).copied()
     }
+
+    /// Compute the output of the circuit from input values without the whole witness.
+    /// Gates are evaluated only until the output cell is filled.
+    pub fn compute_output<F: Field>(&self, public: &[F], private: &[F]) -> Result<F> {
+        let inputs = self.concat_inputs(public, private)?;
+        self.propagate(&inputs, Some(self.output))?[self.output]
+            .ok_or(anyhow!("Output cannot be computed from the inputs."))
+    }
+
+    /// Returns whether a witness satisfying every gate and copy constraint exists for the inputs.
+    /// Same as computing the witness, but returns `false` instead of the error.
+    pub fn is_satisfiable<F: Field>(&self, public: &[F], private: &[F]) -> bool {
+        self.concat_inputs(public, private)
+            .and_then(|inputs| self.solve_witness(&inputs))
+            .is_ok()
+    }
+
+    /// Returns inputs ordered by input number, public inputs first.
+    pub(crate) fn concat_inputs<F: Field>(&self, public: &[F], private: &[F]) -> Result<Vec<F>> {
+        if public.len() != self.input_config.n_pub() || private.len() != self.input_config.n_priv()
+        {
+            return Err(anyhow!(
+                "Expected {} public and {} private inputs, got {} and {}.",
+                self.input_config.n_pub(),
+                self.input_config.n_priv(),
+                public.len(),
+                private.len()
+            ));
+        }
+
+        Ok([public, private].concat())
+    }
+
+    /// Compute the whole witness from `inputs` ordered by input number, public inputs first,
+    /// checking every gate and copy constraint holds.
+    pub(crate) fn solve_witness<F: Field>(&self, inputs: &[F]) -> Result<Vec<F>> {
+        let trace = self.propagate(inputs, None)?;
+
+        // cells left unfilled may be waiting on each other through gates
+        let filled = trace.iter().map(|cell| cell.is_some()).collect::<Vec<_>>();
+        if let Some(cells) = self.find_dependency_cycle(&filled) {
+            return Err(anyhow!("Cyclic dependency among cells {:?}.", cells));
+        }
+
+        let trace = trace
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .ok_or(anyhow!("Not all the cells are filled"))?;
+
+        // gates only fill unknown cells, check the values given through wirings satisfy them
+        for gate in self.gates() {
+            let (lhs, rhs, out) = (trace[gate.lhs], trace[gate.rhs], trace[gate.out]);
+            let value = match gate.op {
+                Op::Add => lhs + rhs,
+                Op::Mul => lhs * rhs,
+            };
+            if value != out {
+                return Err(anyhow!(
+                    "Gate at cell {} in row {} is not satisfied.",
+                    gate.lhs,
+                    gate.row
+                ));
+            }
+        }
+        for class in self.copy_constraints.iter() {
+            if class.iter().any(|id| trace[*id] != trace[class[0]]) {
+                return Err(anyhow!(
+                    "Copy constraint class {:?} holds different values.",
+                    class
+                ));
+            }
+        }
+
+        Ok(trace)
+    }
+
+    /// Find unfilled cells whose gates depend on each other in a cycle.
+    /// Returns all cells of the copy constraint classes on the cycle.
+    fn find_dependency_cycle(&self, filled: &[bool]) -> Option<Vec<usize>> {
+        // cells are grouped by their copy constraint class, named by its first cell
+        let class = |id: usize| self.copy_constraint_class(id).map_or(id, |cells| cells[0]);
+
+        // output of a gate depends on its unfilled operands
+        let mut deps = HashMap::<usize, Vec<usize>>::new();
+        for gate in self.gates() {
+            if filled[gate.out] {
+                continue;
+            }
+            for operand in [gate.lhs, gate.rhs]
+                .into_iter()
+                .filter(|cell| !filled[*cell])
+            {
+                deps.entry(class(gate.out))
+                    .or_default()
+                    .push(class(operand));
+            }
+        }
+
+        let mut visited = HashSet::new();
+        let mut keys = deps.keys().copied().collect::<Vec<_>>();
+        keys.sort();
+        let cycle = keys
+            .into_iter()
+            .find_map(|start| find_cycle(start, &deps, &mut visited, &mut vec![]))?;
+
+        let mut cells = cycle
+            .into_iter()
+            .flat_map(|class| {
+                self.copy_constraint_class(class)
+                    .map_or(vec![class], |cells| cells.to_vec())
+            })
+            .collect::<Vec<_>>();
+        cells.sort();
+        cells.dedup();
+        Some(cells)
+    }
+
+    /// Fill cells gate by gate from `inputs` ordered by input number, public inputs first.
+    /// A gate is evaluated once its operands are filled, so gates may be defined in any order.
+    /// With a `target`, only the gates it depends on are evaluated, until it is filled.
+    /// Cells which could not be filled are `None`.
+    pub(crate) fn propagate<F: Field>(
+        &self,
+        inputs: &[F],
+        target: Option<Id>,
+    ) -> Result<Vec<Option<F>>> {
+        let n_cells = self.n_cells;
+        let gates = &self.gates;
+
+        // gates each cell belongs to, a cell can be the out of a gate and the lhs of the next
+        let mut cell_gates = vec![vec![]; n_cells];
+        for (gate, (id, _)) in gates.iter().enumerate() {
+            for cell in &mut cell_gates[*id..id + 3] {
+                cell.push(gate);
+            }
+        }
+
+        let in_cone = target.map(|target| self.dependency_cone(target));
+
+        let mut trace: Vec<Option<F>> = vec![None; n_cells];
+        let mut eval_queue = VecDeque::<usize>::new();
+
+        // assign a cell and its copy constrained cells, then queue gates using them
+        // Wired cells already filled are kept, contradicting values are found by the final check.
+        let assign =
+            |trace: &mut Vec<Option<F>>, eval_queue: &mut VecDeque<usize>, id: usize, value: F| {
+                trace[id] = Some(value);
+                eval_queue.extend(&cell_gates[id]);
+
+                // cells which are not wired anywhere (e.g. output) have no copy constraints
+                let cell_ids = self.copy_constraint_class(id).unwrap_or(&[]);
+                for cell_id in cell_ids {
+                    if trace[*cell_id].is_none() {
+                        trace[*cell_id] = Some(value);
+                        eval_queue.extend(&cell_gates[*cell_id]);
+                    }
+                }
+            };
+
+        // input i is placed at cell n_cells - (i + 1), constants right after the gate cells
+        let inputs = inputs
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (InputIndex(i).cell(n_cells).0, *value));
+        let constants = self
+            .constant_cells()
+            .map(|(id, value)| (id, value.to_field()));
+        for (id, value) in inputs.chain(constants) {
+            assign(&mut trace, &mut eval_queue, id, value);
+        }
+
+        // loop queue until it's all calculated
+        // Input cells are never evaluated as gate outputs.
+        while let Some(gate) = eval_queue.pop_front() {
+            if target.is_some_and(|target| trace[target].is_some()) {
+                break;
+            }
+            if in_cone.as_ref().is_some_and(|in_cone| !in_cone[gate]) {
+                continue;
+            }
+
+            let (id, op) = gates[gate];
+            let (lhs, rhs, out) = (trace[id], trace[id + 1], trace[id + 2]);
+
+            match (lhs, rhs, out) {
+                (Some(lhs), Some(rhs), None) => {
+                    let value = match op {
+                        Op::Add => lhs + rhs,
+                        Op::Mul => lhs * rhs,
+                    };
+                    assign(&mut trace, &mut eval_queue, id + 2, value);
+                }
+                // rhs can be solved backward: rhs = out - lhs or rhs = out / lhs
+                (Some(lhs), None, Some(out)) => {
+                    let value = match op {
+                        Op::Add => out - lhs,
+                        Op::Mul => {
+                            out * lhs
+                                .inverse()
+                                .ok_or(anyhow!("Division by zero in row {}.", id / 3))?
+                        }
+                    };
+                    assign(&mut trace, &mut eval_queue, id + 1, value);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(trace)
+    }
+
+    /// Returns gate constraints in the order they were added.
+    pub fn gates(&self) -> impl Iterator<Item = GateView> + '_ {
+        self.gates.iter().map(|(id, op)| GateView {
+            row: id / 3,
+            op: *op,
+            lhs: *id,
+            rhs: id + 1,
+            out: id + 2,
+        })
+    }
+
+    /// Check the evaluation domain of the circuit can be constructed over `F`.
+    /// A radix-2 domain holds at most 2^`F::TWO_ADICITY` cells, or a few more if `F` has
+    /// a small subgroup for mixed radix domains. `build` doesn't know the field, so call
+    /// this before setup to fail early instead of deep inside proving.
+    pub fn check_domain_feasible<F: FftField>(&self) -> Result<()> {
+        let domain_size = self
+            .n_cells
+            .checked_next_power_of_two()
+            .ok_or(anyhow!("Circuit size is too large."))?;
+        if GeneralEvaluationDomain::<F>::new(domain_size).is_none() {
+            return Err(anyhow!(
+                "Domain of size {} is not supported by the field of two-adicity {}.",
+                domain_size,
+                F::TWO_ADICITY
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the lhs, rhs and out cells of `row`, which are `row * 3 + {0, 1, 2}`.
+    /// Returns `None` if the circuit has no such row.
+    pub fn row_cells(&self, row: RowIndex) -> Option<(CellId, CellId, CellId)> {
+        let [lhs, rhs, out] = row.cells();
+        (row.0 < self.n_rows).then_some((lhs, rhs, out))
+    }
+
+    /// Returns the cell of `input`, or `None` if the circuit has no such input.
+    pub fn input_cell(&self, input: InputIndex) -> Option<CellId> {
+        (input.0 < self.n_inputs()).then(|| input.cell(self.n_cells))
+    }
+
+    /// Returns Z_input(X) = ∏(X - 𝜔^id) over the public input and constant cells,
+    /// where T has to agree with the public input polynomial.
+    /// Setup commits to it, and the prover divides by it in the input zero test.
+    pub fn input_vanishing_polynomial<F: FftField>(&self) -> Result<DensePolynomial<F>> {
+        let domain_size = self.n_cells.checked_next_power_of_two().unwrap();
+        let domain = GeneralEvaluationDomain::<F>::new(domain_size)
+            .ok_or(anyhow!("Domain cannot be constructed from circuit size"))?;
+
+        // public input and constant cells
+        let input_ids = (0..self.input_config.n_pub())
+            .map(|i| InputIndex(i).cell(self.n_cells).0)
+            .chain(self.constant_cells().map(|(id, _)| id))
+            .collect::<Vec<_>>();
+        Ok(vanishing_over_cells(domain, &input_ids))
+    }
+
+    /// Returns pairs of id and value of the constant cells.
+    pub(crate) fn constant_cells(&self) -> impl Iterator<Item = (Id, &Constant)> + '_ {
+        let offset = self.n_rows * 3;
+        self.constants
+            .iter()
+            .enumerate()
+            .map(move |(i, value)| (offset + i, value))
+    }
+
+    /// Export the circuit as a Graphviz DOT graph.
+    /// Inputs, constants and gates are nodes, and edges follow copy constraints
+    /// from the cell producing a value to the gate operand using it.
+    pub fn to_dot(&self) -> String {
+        let n_pub = self.input_config.n_pub();
+        let gate_cells = self.n_rows * 3;
+
+        // node name and label of a cell which produces a value
+        let producer = |id: Id| -> Option<String> {
+            if self.input_cell_ids().any(|cell| cell.0 == id) {
+                Some(format!("input_{}", CellId(id).input_index(self.n_cells).0))
+            } else if id >= gate_cells {
+                Some(format!("const_{}", id - gate_cells))
+            } else {
+                self.gates
+                    .iter()
+                    .position(|(gate_id, _)| gate_id + 2 == id)
+                    .map(|gate| format!("gate_{}", gate))
+            }
+        };
+
+        let mut dot = String::from("digraph circuit {\n");
+        for i in 0..self.n_inputs() {
+            let label = if i < n_pub {
+                format!("pub_{}", i)
+            } else {
+                format!("priv_{}", i - n_pub)
+            };
+            dot.push_str(&format!(
+                "    input_{} [label=\"{}\", shape=box];\n",
+                i, label
+            ));
+        }
+        for (i, value) in self.constants.iter().enumerate() {
+            dot.push_str(&format!(
+                "    const_{} [label=\"{}\", shape=diamond];\n",
+                i, value
+            ));
+        }
+        for (gate, (id, op)) in self.gates.iter().enumerate() {
+            let shape = if id + 2 == self.output {
+                "doublecircle"
+            } else {
+                "circle"
+            };
+            dot.push_str(&format!(
+                "    gate_{} [label=\"{:?}\", shape={}];\n",
+                gate, op, shape
+            ));
+        }
+        for (gate, (gate_id, _)) in self.gates.iter().enumerate() {
+            for (id, operand) in [(*gate_id, "lhs"), (gate_id + 1, "rhs")] {
+                // operand may be the output of the previous gate in a wide sum
+                let class = self.copy_constraint_class(id).unwrap_or(&[]);
+                let from = producer(id).into_iter().chain(
+                    class
+                        .iter()
+                        .filter(|c| **c != id)
+                        .filter_map(|c| producer(*c)),
+                );
+                for from in from {
+                    dot.push_str(&format!(
+                        "    {} -> gate_{} [label=\"{}\"];\n",
+                        from, gate, operand
+                    ));
+                }
+            }
+        }
+        dot.push_str("}\n");
+
+        dot
+    }
+
+    /// Translate the circuit into rank-1 constraints over the cells.
+    /// Returns constraints and the number of cell variables.
+    ///
+    /// * addition gate: (lhs + rhs) ⋅ 1 = out
+    /// * multiplication gate: lhs ⋅ rhs = out
+    /// * constant cell: x ⋅ 1 = value
+    /// * copy constraint: (x - y) ⋅ 1 = 0 for each adjacent pair of cells in a wiring
+    pub fn to_r1cs<F: Field>(&self) -> (Vec<Constraint<F>>, usize) {
+        let one = vec![(F::one(), Variable::One)];
+        let cell = |id: Id| (F::one(), Variable::Cell(id));
+
+        let gates = self.gates.iter().map(|(id, op)| {
+            let (lhs, rhs, out) = (cell(*id), cell(id + 1), cell(id + 2));
+            match op {
+                Op::Add => Constraint {
+                    a: vec![lhs, rhs],
+                    b: one.clone(),
+                    c: vec![out],
+                },
+                Op::Mul => Constraint {
+                    a: vec![lhs],
+                    b: vec![rhs],
+                    c: vec![out],
+                },
+            }
+        });
+
+        let constants = self.constant_cells().map(|(id, value)| Constraint {
+            a: vec![cell(id)],
+            b: one.clone(),
+            c: vec![(value.to_field(), Variable::One)],
+        });
+
+        let wires = self.copy_constraints.iter().flat_map(|wiring| {
+            wiring.windows(2).map(|pair| Constraint {
+                a: vec![cell(pair[0]), (-F::one(), Variable::Cell(pair[1]))],
+                b: one.clone(),
+                c: vec![],
+            })
+        });
+
+        (gates.chain(constants).chain(wires).collect(), self.n_cells)
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Cellref {
     Input(Id),
     Wire(Id),
+    /// Index of a constant added by `CircuitBuilder::add_constant`.
+    Constant(usize),
+}
+
+/// Refs to the public inputs of a circuit, indexed by public input number.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PublicInputs(Vec<Cellref>);
+
+/// Refs to the private inputs of a circuit, indexed by private input number.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PrivateInputs(Vec<Cellref>);
+
+macro_rules! impl_input_refs {
+    ($name:ident) => {
+        impl $name {
+            /// Returns the ref to the `index`-th input.
+            pub fn get(&self, index: usize) -> Option<Cellref> {
+                self.0.get(index).copied()
+            }
+
+            pub fn len(&self) -> usize {
+                self.0.len()
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.0.is_empty()
+            }
+
+            pub fn iter(&self) -> impl Iterator<Item = Cellref> + '_ {
+                self.0.iter().copied()
+            }
+        }
+    };
 }
 
+impl_input_refs!(PublicInputs);
+impl_input_refs!(PrivateInputs);
+
 pub struct CircuitBuilder {
     current_row: usize,
     ops: Vec<Op>,
     /// Store which two cells are equal
     wiring_pairs: Vec<(Cellref, Cellref)>,
     input_config: InputConfig,
+    constants: Vec<Constant>,
+    gates: Vec<(Id, Op)>,
+    /// Selector on each gate cell, updated as gates are added.
+    selector_evals: Vec<bool>,
+    input_names: HashMap<String, usize>,
 }
 
 impl CircuitBuilder {
@@ -125,81 +1134,437 @@ impl CircuitBuilder {
             ops: vec![],
             wiring_pairs: vec![],
             input_config,
+            constants: vec![],
+            gates: vec![],
+            selector_evals: vec![],
+            input_names: HashMap::new(),
+        }
+    }
+
+    /// Name `input`, so its value can be assigned by the name with `Prover::assign`
+    /// or `InputAssignment::with_labels`.
+    /// Returns error if the ref is not an existing input or the name is taken.
+    pub fn name_input(&mut self, input: Cellref, name: &str) -> Result<()> {
+        let Cellref::Input(number) = input else {
+            return Err(anyhow!("{:?} is not an input.", input));
+        };
+        self.validate_cell_ref(input)?;
+        if self.input_names.contains_key(name) {
+            return Err(anyhow!("Name {} is already used.", name));
+        }
+
+        self.input_names.insert(name.to_string(), number - 1);
+        Ok(())
+    }
+
+    /// Add inputs fixed to zero until there are `n_pub` public and `n_priv` private inputs,
+    /// so circuits of fewer inputs share the same input shape.
+    /// Private inputs follow the public ones, so refs to private inputs taken before
+    /// padding public inputs are stale. Get them again with `get_input_refs`.
+    /// Returns error if there are already more inputs.
+    pub fn pad_inputs_to(&mut self, n_pub: usize, n_priv: usize) -> Result<()> {
+        let (old_pub, old_priv) = (self.input_config.n_pub(), self.input_config.n_priv());
+        if n_pub < old_pub || n_priv < old_priv {
+            return Err(anyhow!(
+                "Cannot pad {} public and {} private inputs to {} and {}.",
+                old_pub,
+                old_priv,
+                n_pub,
+                n_priv
+            ));
+        }
+
+        // shift wirings and names of private inputs past the new public inputs
+        let shift = n_pub - old_pub;
+        let shift_ref = |cell| match cell {
+            Cellref::Input(number) if number > old_pub => Cellref::Input(number + shift),
+            cell => cell,
+        };
+        for (x, y) in self.wiring_pairs.iter_mut() {
+            (*x, *y) = (shift_ref(*x), shift_ref(*y));
+        }
+        for number in self.input_names.values_mut() {
+            if *number >= old_pub {
+                *number += shift;
+            }
+        }
+        self.input_config = InputConfig::new(n_pub, n_priv);
+
+        let padding = (old_pub..n_pub)
+            .chain(n_pub + old_priv..n_pub + n_priv)
+            .collect::<Vec<_>>();
+        if !padding.is_empty() {
+            let zero = self.add_constant(0);
+            for number in padding {
+                self.add_wire_constraint(Cellref::Input(number + 1), zero)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns pair of vec of input refs.
+    /// First item is public inputs' refs and second item is private inputs' refs.
+    pub fn get_input_refs(&self) -> (Vec<Cellref>, Vec<Cellref>) {
+        let pb_len = self.input_config.n_pub();
+        let prv_len = self.input_config.n_priv();
+
+        let pb = (1..=pb_len + 1).map(Cellref::Input).collect::<Vec<_>>();
+        let prv = (pb_len + 1..=pb_len + prv_len)
+            .map(Cellref::Input)
+            .collect::<Vec<_>>();
+
+        (pb, prv)
+    }
+
+    /// Returns refs of public and private inputs in distinct types so they can't be mixed up.
+    pub fn get_typed_input_refs(&self) -> (PublicInputs, PrivateInputs) {
+        let pb_len = self.input_config.n_pub();
+        let prv_len = self.input_config.n_priv();
+
+        let pb = (1..=pb_len).map(Cellref::Input).collect();
+        let prv = (pb_len + 1..=pb_len + prv_len)
+            .map(Cellref::Input)
+            .collect();
+
+        (PublicInputs(pb), PrivateInputs(prv))
+    }
+
+    /// Add new addition gate constraint to a circuit.
+    pub fn add_addition(&mut self, lhs: Cellref, rhs: Cellref) -> Result<Cellref> {
+        self.validate_cell_ref(lhs).context("LHS")?;
+        self.validate_cell_ref(rhs).context("RHS")?;
+
+        let pos = self.add_row(Op::Add);
+
+        // add wiring constraints
+        let new_lhs = Cellref::Wire(pos);
+        let new_rhs = Cellref::Wire(pos + 1);
+
+        self.add_wire_constraint(lhs, new_lhs)?;
+        self.add_wire_constraint(rhs, new_rhs)?;
+
+        Ok(Cellref::Wire(pos + 2))
+    }
+
+    /// Add new multiplication gate constraint to a circuit.
+    pub fn add_multiplication(&mut self, lhs: Cellref, rhs: Cellref) -> Result<Cellref> {
+        self.validate_cell_ref(lhs).context("LHS")?;
+        self.validate_cell_ref(rhs).context("RHS")?;
+
+        let pos = self.add_row(Op::Mul);
+
+        // add wiring constraints
+        let new_lhs = Cellref::Wire(pos);
+        let new_rhs = Cellref::Wire(pos + 1);
+
+        self.add_wire_constraint(lhs, new_lhs)?;
+        self.add_wire_constraint(rhs, new_rhs)?;
+
+        Ok(Cellref::Wire(pos + 2))
+    }
+
+    /// Add a multiplication of `x` by `scalar` fixed in the circuit.
+    /// The scalar is a constant cell, which the verifier fixes like every constant,
+    /// so it takes no input and the prover can't choose it.
+    pub fn add_scalar_multiplication(&mut self, x: Cellref, scalar: u64) -> Result<Cellref> {
+        let scalar = self.add_constant(scalar);
+        self.add_multiplication(x, scalar)
+    }
+
+    /// Add the rows of `gate` to a circuit after the current row.
+    /// `inputs` are referred to by `GateOperand::Input` of the rows.
+    /// Returns the out cell of the last row.
+    pub fn add_gate_layout(
+        &mut self,
+        gate: &dyn GateLayout,
+        inputs: &[Cellref],
+    ) -> Result<Cellref> {
+        for (i, input) in inputs.iter().enumerate() {
+            self.validate_cell_ref(*input)
+                .with_context(|| format!("Input {}", i))?;
+        }
+        gate.validate(inputs.len())?;
+
+        let mut outs: Vec<Cellref> = vec![];
+        for (op, lhs, rhs) in gate.rows() {
+            let cell = |operand| match operand {
+                GateOperand::Input(i) => inputs[i],
+                GateOperand::Row(j) => outs[j],
+            };
+            let (lhs, rhs) = (cell(lhs), cell(rhs));
+            let out = match op {
+                Op::Add => self.add_addition(lhs, rhs)?,
+                Op::Mul => self.add_multiplication(lhs, rhs)?,
+            };
+            outs.push(out);
+        }
+
+        Ok(outs[outs.len() - 1])
+    }
+
+    /// Add 2-to-1 multiplexer to a circuit.
+    /// Returns `a` when `sel` is 1 and `b` when `sel` is 0.
+    ///
+    /// Computes `out = b + sel * (a - b)` with following rows.
+    ///
+    /// | lhs | rhs | out | s   |
+    /// |-----|-----|-----|-----|
+    /// | sel | sel | sel | Mul | sel is boolean
+    /// | b   | d   | a   | Add | d = a - b
+    /// | d   | sel | e   | Mul |
+    /// | b   | e   | out | Add |
+    pub fn add_mux(&mut self, sel: Cellref, a: Cellref, b: Cellref) -> Result<Cellref> {
+        self.validate_cell_ref(sel).context("SEL")?;
+        self.validate_cell_ref(a).context("A")?;
+        self.validate_cell_ref(b).context("B")?;
+
+        // assert sel * sel = sel
+        let pos = self.add_row(Op::Mul);
+        self.add_wire_constraint(sel, Cellref::Wire(pos))?;
+        self.add_wire_constraint(sel, Cellref::Wire(pos + 1))?;
+        self.add_wire_constraint(sel, Cellref::Wire(pos + 2))?;
+
+        // b + d = a, rhs is filled backward by the prover
+        let pos = self.add_row(Op::Add);
+        self.add_wire_constraint(b, Cellref::Wire(pos))?;
+        self.add_wire_constraint(a, Cellref::Wire(pos + 2))?;
+        let d = Cellref::Wire(pos + 1);
+
+        let e = self.add_multiplication(d, sel)?;
+        self.add_addition(b, e)
+    }
+
+    /// Add sum of all `terms` to a circuit as a single wide addition.
+    ///
+    /// Each partial sum is shared as the out of a gate and the lhs of the next,
+    /// so `n` terms take 2n - 1 cells instead of 3(n - 1) cells of chained additions.
+    /// Leading cells are padded with 0 to fill rows.
+    ///
+    /// | c0  | c1  | c2  | c3  | c4  | c5  | c6  | c7  | c8  |
+    /// |-----|-----|-----|-----|-----|-----|-----|-----|-----|
+    /// | t0  | t1  | s1  | t2  | s2  | t3  | s3  | t4  | out |
+    pub fn add_wide_sum(&mut self, terms: &[Cellref]) -> Result<Cellref> {
+        if terms.len() < 2 {
+            return Err(anyhow!("Wide sum needs at least two terms."));
+        }
+        for (i, term) in terms.iter().enumerate() {
+            self.validate_cell_ref(*term)
+                .with_context(|| format!("Term {}", i))?;
+        }
+
+        let n_cells = 2 * terms.len() - 1;
+        let n_rows = n_cells.div_ceil(3);
+        let start = self.current_row * 3;
+        for _ in 0..n_rows {
+            self.ops.push(Op::Add);
+        }
+        self.current_row += n_rows;
+        self.selector_evals.resize(self.current_row * 3, false);
+
+        let pad = n_rows * 3 - n_cells;
+        if pad > 0 {
+            let zero = self.add_constant(0);
+            for id in start..start + pad {
+                self.add_wire_constraint(zero, Cellref::Wire(id))?;
+            }
+        }
+
+        // partial sum s_j = s_(j-1) + t_j where s_0 = t_0
+        let pos = start + pad;
+        self.add_wire_constraint(terms[0], Cellref::Wire(pos))?;
+        for (j, term) in terms.iter().enumerate().skip(1) {
+            let gate = pos + 2 * (j - 1);
+            self.push_gate(gate, Op::Add);
+            self.add_wire_constraint(*term, Cellref::Wire(gate + 1))?;
+        }
+
+        Ok(Cellref::Wire(start + n_rows * 3 - 1))
+    }
+
+    /// Add a cell fixed to `value`.
+    /// The same cell is returned for the same value.
+    pub fn add_constant(&mut self, value: u64) -> Cellref {
+        self.push_constant(value.into())
+    }
+
+    /// Add a cell fixed to the field element `value`, e.g. one which doesn't fit in a u64.
+    /// The same cell is returned for the same value.
+    pub fn add_field_constant<F: PrimeField>(&mut self, value: F) -> Cellref {
+        self.push_constant(Constant::from_field(value))
+    }
+
+    fn push_constant(&mut self, value: Constant) -> Cellref {
+        let index = match self.constants.iter().position(|c| *c == value) {
+            Some(index) => index,
+            None => {
+                self.constants.push(value);
+                self.constants.len() - 1
+            }
+        };
+        Cellref::Constant(index)
+    }
+
+    /// Add the output of a previous circuit as a constant cell.
+    /// `prev_output` is usually the claimed output of a proof of the previous circuit,
+    /// so the two proofs together prove the sequential computation.
+    pub fn bind_external_output<F: PrimeField>(&mut self, prev_output: F) -> Cellref {
+        self.add_field_constant(prev_output)
+    }
+
+    /// Constrain the sum of `coeff * cell` over `terms` to be zero, e.g. a + 2b - 3c = 0.
+    ///
+    /// The gate has no coefficients, so each term is multiplied by a constant cell.
+    /// Terms with small negative coefficients, like -1, are moved to the right side
+    /// with the coefficient negated, and the wide sums of both sides are wired together.
+    ///
+    /// | lhs | rhs | out | s   |
+    /// |-----|-----|-----|-----|
+    /// | b   | 2   | 2b  | Mul |
+    /// | c   | 3   | 3c  | Mul |
+    /// | a   | 2b  | 3c  | Add |
+    pub fn add_linear_constraint<F: PrimeField>(&mut self, terms: &[(F, Cellref)]) -> Result<()> {
+        if terms.is_empty() {
+            return Err(anyhow!("Linear constraint needs at least one term."));
+        }
+
+        let (mut lhs, mut rhs) = (vec![], vec![]);
+        for (i, (coeff, cell)) in terms.iter().enumerate() {
+            self.validate_cell_ref(*cell)
+                .with_context(|| format!("Term {}", i))?;
+            let (side, coeff) = match to_u64(-*coeff) {
+                Some(_) if !coeff.is_zero() => (&mut rhs, -*coeff),
+                _ => (&mut lhs, *coeff),
+            };
+            if coeff.is_one() {
+                side.push(*cell);
+            } else if !coeff.is_zero() {
+                let constant = self.add_field_constant(coeff);
+                side.push(self.add_multiplication(*cell, constant)?);
+            }
         }
+
+        let lhs = self.add_sum(&lhs)?;
+        let rhs = self.add_sum(&rhs)?;
+        self.add_wire_constraint(lhs, rhs)
     }
 
-    /// Returns pair of vec of input refs.
-    /// First item is public inputs' refs and second item is private inputs' refs.
-    pub fn get_input_refs(&self) -> (Vec<Cellref>, Vec<Cellref>) {
-        let pb_len = self.input_config.n_pub();
-        let prv_len = self.input_config.n_priv();
+    /// Returns a cell holding the sum of `terms`, which is 0 if there are no terms.
+    fn add_sum(&mut self, terms: &[Cellref]) -> Result<Cellref> {
+        match terms {
+            [] => Ok(self.add_constant(0)),
+            [term] => Ok(*term),
+            _ => self.add_wide_sum(terms),
+        }
+    }
 
-        let pb = (1..=pb_len + 1).map(Cellref::Input).collect::<Vec<_>>();
-        let prv = (pb_len + 1..=pb_len + prv_len)
-            .map(Cellref::Input)
-            .collect::<Vec<_>>();
+    /// Add division `a / b` to a circuit.
+    /// Inverse of `b` is a witness hint computed by the prover,
+    /// so witness calculation fails if `b` is zero.
+    ///
+    /// | lhs | rhs | out | s   |
+    /// |-----|-----|-----|-----|
+    /// | b   | inv | 1   | Mul | inv is solved backward
+    /// | a   | inv | out | Mul |
+    pub fn add_division(&mut self, a: Cellref, b: Cellref) -> Result<Cellref> {
+        self.validate_cell_ref(a).context("A")?;
+        self.validate_cell_ref(b).context("B")?;
 
-        (pb, prv)
+        let inv = self.add_inverse(b)?;
+        self.add_multiplication(a, inv)
     }
 
-    /// Add new addition gate constraint to a circuit.
-    pub fn add_addition(&mut self, lhs: Cellref, rhs: Cellref) -> Result<Cellref> {
-        self.validate_cell_ref(lhs)
-            .map_err(|e| anyhow!(format!("LHS: {}", e)))?;
-        self.validate_cell_ref(rhs)
-            .map_err(|e| anyhow!(format!("RHS: {}", e)))?;
+    /// Constrain `x` to be nonzero by proving knowledge of its inverse.
+    /// Inverse of `x` is a witness hint computed by the prover,
+    /// so witness calculation fails if `x` is zero.
+    ///
+    /// | lhs | rhs | out | s   |
+    /// |-----|-----|-----|-----|
+    /// | x   | inv | 1   | Mul | inv is solved backward
+    pub fn add_nonzero_constraint(&mut self, x: Cellref) -> Result<()> {
+        self.validate_cell_ref(x)?;
+        self.add_inverse(x)?;
+        Ok(())
+    }
 
-        self.ops.push(Op::Add);
-        let pos = self.current_row * 3;
-        self.current_row += 1;
+    /// Add a row constraining x * inv = 1 and returns the inverse cell.
+    fn add_inverse(&mut self, x: Cellref) -> Result<Cellref> {
+        let one = self.add_constant(1);
+        let pos = self.add_row(Op::Mul);
+        self.add_wire_constraint(x, Cellref::Wire(pos))?;
+        self.add_wire_constraint(one, Cellref::Wire(pos + 2))?;
+        Ok(Cellref::Wire(pos + 1))
+    }
 
-        // add wiring constraints
-        let new_lhs = Cellref::Wire(pos);
-        let new_rhs = Cellref::Wire(pos + 1);
+    /// Assert a wire equals to the public input at `public_index`.
+    /// The wire and the public input cell share the same value by copy constraint,
+    /// so the verifier checks it through the input constraint.
+    pub fn assert_equals_public(&mut self, wire: Cellref, public_index: usize) -> Result<()> {
+        self.validate_cell_ref(wire)?;
+        if public_index >= self.input_config.n_pub() {
+            return Err(anyhow!("Public input {} does not exist.", public_index));
+        }
 
-        self.add_wire_constraint(lhs, new_lhs);
-        self.add_wire_constraint(rhs, new_rhs);
+        self.add_wire_constraint(wire, Cellref::Input(public_index + 1))
+    }
 
-        Ok(Cellref::Wire(pos + 2))
+    /// Assert the output of the last row added so far equals the public input at `public_index`.
+    /// Proves f(private) = public_y when called after the gates computing f.
+    pub fn assert_output_equals(&mut self, public_index: usize) -> Result<()> {
+        if self.current_row == 0 {
+            return Err(anyhow!("Circuit has no output yet."));
+        }
+        self.assert_equals_public(Cellref::Wire(self.current_row * 3 - 1), public_index)
     }
 
-    /// Add new multiplication gate constraint to a circuit.
-    pub fn add_multiplication(&mut self, lhs: Cellref, rhs: Cellref) -> Result<Cellref> {
-        self.validate_cell_ref(lhs)
-            .map_err(|e| anyhow!(format!("LHS: {}", e)))?;
-        self.validate_cell_ref(rhs)
-            .map_err(|e| anyhow!(format!("RHS: {}", e)))?;
+    /// Assert wires `a` and `b` hold the same value, e.g. to check two subcircuits are
+    /// equivalent. They join the same copy constraint class, so witness generation
+    /// rejects inputs giving them different values and the permutation argument proves it.
+    pub fn assert_eq_wires(&mut self, a: Cellref, b: Cellref) -> Result<()> {
+        self.add_wire_constraint(a, b)
+    }
 
-        self.ops.push(Op::Mul);
+    /// Add a row with single gate constraint and returns id of its first cell.
+    fn add_row(&mut self, op: Op) -> Id {
         let pos = self.current_row * 3;
+        self.ops.push(op);
         self.current_row += 1;
+        self.selector_evals.resize(self.current_row * 3, false);
+        self.push_gate(pos, op);
+        pos
+    }
 
-        // add wiring constraints
-        let new_lhs = Cellref::Wire(pos);
-        let new_rhs = Cellref::Wire(pos + 1);
-
-        self.add_wire_constraint(lhs, new_lhs);
-        self.add_wire_constraint(rhs, new_rhs);
-
-        Ok(Cellref::Wire(pos + 2))
+    /// Add a gate constraint on cells already laid out in rows.
+    fn push_gate(&mut self, id: Id, op: Op) {
+        self.gates.push((id, op));
+        self.selector_evals[id] = op == Op::Add;
     }
 
     /// Add wire constraint to a circuit.
-    pub fn add_wire_constraint(&mut self, x: Cellref, y: Cellref) {
-        self.wiring_pairs.push((x, y))
+    /// Returns error if either cell does not exist yet.
+    pub fn add_wire_constraint(&mut self, x: Cellref, y: Cellref) -> Result<()> {
+        self.validate_cell_ref(x).context("X")?;
+        self.validate_cell_ref(y).context("Y")?;
+
+        self.wiring_pairs.push((x, y));
+        Ok(())
     }
 
-    fn validate_cell_ref(&self, cell: Cellref) -> Result<()> {
+    fn validate_cell_ref(&self, cell: Cellref) -> Result<(), CircuitBuildError> {
         let n_input = self.input_config.total_input();
         match cell {
             Cellref::Input(x) => {
                 if x == 0 || x > n_input {
-                    return Err(anyhow!("Input {} does not exist.", x));
+                    return Err(CircuitBuildError::InvalidInputRef(x));
                 }
             }
             Cellref::Wire(x) => {
                 if x >= self.current_row * 3 {
-                    return Err(anyhow!("Wire {} does not exist.", x));
+                    return Err(CircuitBuildError::InvalidWireRef(x));
+                }
+            }
+            Cellref::Constant(x) => {
+                if x >= self.constants.len() {
+                    return Err(CircuitBuildError::InvalidConstantRef(x));
                 }
             }
         };
@@ -207,65 +1572,164 @@ impl CircuitBuilder {
         Ok(())
     }
 
-    pub fn build(self) -> Result<Circuit> {
+    pub fn build(self) -> Result<Circuit, CircuitBuildError> {
+        self.snapshot()
+    }
+
+    /// Build a circuit from the gates added so far without consuming the builder.
+    /// Gates can still be added after taking a snapshot.
+    pub fn snapshot(&self) -> Result<Circuit, CircuitBuildError> {
+        if self.current_row == 0 {
+            return Err(CircuitBuildError::NoGates);
+        }
+
         let n_input = self.input_config.total_input();
-        let n_cells = n_input + self.current_row * 3;
+        let n_constants = self.constants.len();
+        let n_cells = n_input + n_constants + self.current_row * 3;
 
         // calculate every wirings
         let mut wirings = Vec::with_capacity(n_input);
         for input_number in 1..=n_input {
             // push empty hash set for each input cell
-            let id = n_cells - input_number;
+            let id = InputIndex(input_number - 1).cell(n_cells).0;
             let mut set = HashSet::new();
             set.insert(id);
             wirings.push(set);
         }
+        for index in 0..n_constants {
+            let mut set = HashSet::new();
+            set.insert(self.current_row * 3 + index);
+            wirings.push(set);
+        }
 
-        self.wiring_pairs.iter().for_each(|(x_ref, y_ref)| {
+        // Each cell is in at most one set. A pair joining cells of two sets merges the sets,
+        // so wirings like A = B followed by C = A end up in a single class.
+        let constant_cells = self.current_row * 3..self.current_row * 3 + n_constants;
+        for (x_ref, y_ref) in self.wiring_pairs.iter() {
             let x = match x_ref {
                 Cellref::Wire(x) => *x,
-                Cellref::Input(x) => n_cells - x,
+                Cellref::Input(x) => InputIndex(x - 1).cell(n_cells).0,
+                Cellref::Constant(x) => self.current_row * 3 + x,
             };
             let y = match y_ref {
                 Cellref::Wire(y) => *y,
-                Cellref::Input(y) => n_cells - y,
+                Cellref::Input(y) => InputIndex(y - 1).cell(n_cells).0,
+                Cellref::Constant(y) => self.current_row * 3 + y,
             };
 
-            if let Some(wire_set) = wirings.iter_mut().find(|set| set.contains(&x)) {
-                wire_set.insert(y);
-            } else if let Some(wire_set) = wirings.iter_mut().find(|set| set.contains(&y)) {
-                wire_set.insert(x);
-            } else {
-                let mut set = HashSet::new();
-                set.insert(x);
-                set.insert(y);
-                wirings.push(set);
+            let x_set = wirings.iter().position(|set| set.contains(&x));
+            let y_set = wirings.iter().position(|set| set.contains(&y));
+            match (x_set, y_set) {
+                (Some(i), Some(j)) if i != j => {
+                    // both cells are already wired, merge two sets into one
+                    let merged = wirings.swap_remove(i.max(j));
+                    let set = &mut wirings[i.min(j)];
+                    set.extend(merged);
+
+                    // two different constants can not share the same value
+                    if set.iter().filter(|id| constant_cells.contains(id)).count() > 1 {
+                        return Err(CircuitBuildError::ConflictingConstraint(*x_ref, *y_ref));
+                    }
+                }
+                (Some(_), Some(_)) => {}
+                (Some(i), None) => {
+                    wirings[i].insert(y);
+                }
+                (None, Some(j)) => {
+                    wirings[j].insert(x);
+                }
+                (None, None) => {
+                    let mut set = HashSet::new();
+                    set.insert(x);
+                    set.insert(y);
+                    wirings.push(set);
+                }
             }
-        });
+        }
 
         let output = self.current_row * 3 - 1;
+        debug_assert_eq!(self.ops.len(), self.current_row);
+
+        // sort cells in each class and classes by their smallest cell,
+        // so the result doesn't depend on the order sets were merged.
+        let mut copy_constraints = wirings
+            .iter()
+            .map(|set| {
+                let mut v = set.iter().copied().collect::<Vec<_>>();
+                v.sort();
+                v
+            })
+            .collect::<Vec<Vec<_>>>();
+        copy_constraints.sort_by_key(|class| class[0]);
+        debug_assert_eq!(
+            copy_constraints.iter().map(Vec::len).sum::<usize>(),
+            copy_constraints
+                .iter()
+                .flatten()
+                .collect::<HashSet<_>>()
+                .len(),
+            "Copy constraint classes should be disjoint"
+        );
 
-        Ok(Circuit {
+        let circuit = Circuit {
             input_config: self.input_config,
-            selectors: self.ops,
+            selectors: self.ops.clone(),
+            gates: self.gates.clone(),
+            selector_evals: self.selector_evals.clone(),
+            constants: self.constants.clone(),
             n_cells,
             n_rows: self.current_row,
-            copy_constraints: wirings
-                .iter()
-                .map(|set| {
-                    let mut v = set.iter().copied().collect::<Vec<_>>();
-                    v.sort();
-                    v
-                })
-                .collect::<Vec<Vec<_>>>(),
+            copy_constraints,
             output,
-        })
+            input_names: self.input_names.clone(),
+        };
+        circuit.validate_invariants()?;
+
+        Ok(circuit)
+    }
+}
+
+/// Depth first search for a cycle reachable from `node`.
+/// `path` holds the nodes on the current search path.
+fn find_cycle(
+    node: usize,
+    deps: &HashMap<usize, Vec<usize>>,
+    visited: &mut HashSet<usize>,
+    path: &mut Vec<usize>,
+) -> Option<Vec<usize>> {
+    if let Some(pos) = path.iter().position(|n| *n == node) {
+        return Some(path[pos..].to_vec());
     }
+    if !visited.insert(node) {
+        return None;
+    }
+
+    path.push(node);
+    for next in deps.get(&node).into_iter().flatten() {
+        if let Some(cycle) = find_cycle(*next, deps, visited, path) {
+            return Some(cycle);
+        }
+    }
+    path.pop();
+    None
+}
+
+/// Returns `x` as u64 if it fits in one.
+fn to_u64<F: PrimeField>(x: F) -> Option<u64> {
+    let bigint = x.into_bigint();
+    let limbs = bigint.as_ref();
+    if limbs[1..].iter().any(|limb| *limb != 0) {
+        return None;
+    }
+    Some(limbs[0])
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::simple_circuit;
+    use ark_bls12_381::{Fq, Fr};
+    use ark_poly::Polynomial;
 
     // Test simple circuit to calculate
     // out = (pub_0 + priv_0) * pub_1 + priv_0
@@ -311,26 +1775,381 @@ mod tests {
         );
 
         // Test wiring constraints
-        // Wirings: [[0, 11],[4,10],[1,7,9],[2,3],[5,6]]
+        // Wirings: [[0, 11],[1,7,9],[2,3],[4,10],[5,6]]
         assert!(
             circ.copy_constraints.eq(&vec![
                 vec![0, 11],
-                vec![4, 10],
                 vec![1, 7, 9],
                 vec![2, 3],
+                vec![4, 10],
                 vec![5, 6],
             ]),
             "Circuit wirings should be defined correctly."
         );
     }
 
+    #[test]
+    fn test_build_circuit_with_typed_input_refs() {
+        let mut builder = CircuitBuilder::new(InputConfig::new(2, 1));
+        let (public, private) = builder.get_typed_input_refs();
+        assert_eq!((public.len(), private.len()), (2, 1));
+        assert_eq!(private.get(1), None);
+
+        let out_0 = builder
+            .add_addition(public.get(0).unwrap(), private.get(0).unwrap())
+            .unwrap();
+        let out_1 = builder
+            .add_multiplication(out_0, public.get(1).unwrap())
+            .unwrap();
+        let _ = builder
+            .add_addition(out_1, private.get(0).unwrap())
+            .unwrap();
+        let circ = builder.build().unwrap();
+
+        assert_eq!(circ.selectors, vec![Op::Add, Op::Mul, Op::Add]);
+        assert_eq!(
+            circ.copy_constraints,
+            vec![
+                vec![0, 11],
+                vec![1, 7, 9],
+                vec![2, 3],
+                vec![4, 10],
+                vec![5, 6],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_copy_constraints_ordering_is_deterministic() {
+        let build = || {
+            let mut builder = CircuitBuilder::new(InputConfig::new(2, 1));
+            let (pb_refs, prv_refs) = builder.get_input_refs();
+            let out_0 = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
+            let out_1 = builder.add_multiplication(out_0, pb_refs[1]).unwrap();
+            let out_2 = builder.add_addition(out_1, prv_refs[0]).unwrap();
+            // joins out_2 to the class of pub_0
+            builder.add_wire_constraint(pb_refs[0], out_2).unwrap();
+            builder.build().unwrap()
+        };
+
+        let circ = build();
+        assert_eq!(circ.copy_constraints, build().copy_constraints);
+        assert!(circ
+            .copy_constraints
+            .windows(2)
+            .all(|pair| pair[0][0] < pair[1][0]));
+    }
+
+    #[test]
+    fn test_input_wired_into_many_gates() {
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let out_0 = builder.add_addition(prv_refs[0], pb_refs[0]).unwrap();
+        let out_1 = builder.add_multiplication(out_0, prv_refs[0]).unwrap();
+        let out_2 = builder.add_addition(prv_refs[0], out_1).unwrap();
+        let out_3 = builder.add_multiplication(prv_refs[0], out_2).unwrap();
+
+        // priv_0 at cell 12 and its four uses form a single class
+        let circ = builder.snapshot().unwrap();
+        let cells = |ids: &[usize]| Some(ids.iter().copied().map(CellId).collect::<Vec<_>>());
+        assert_eq!(
+            circ.get_copy_constraints(CellId(12)),
+            cells(&[0, 4, 6, 9, 12])
+        );
+
+        // out_3 = out_0 joins [2, 3], then out_2 = out_3 joins [8, 10] transitively
+        builder.add_wire_constraint(out_3, out_0).unwrap();
+        builder.add_wire_constraint(out_2, out_3).unwrap();
+        let circ = builder.build().unwrap();
+        assert_eq!(
+            circ.get_copy_constraints(CellId(8)),
+            cells(&[2, 3, 8, 10, 11])
+        );
+        assert_eq!(circ.get_copy_constraints(CellId(12)).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut builder = CircuitBuilder::new(InputConfig::new(2, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let out_0 = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
+        let out_1 = builder.add_multiplication(out_0, pb_refs[1]).unwrap();
+
+        let snapshot = builder.snapshot().unwrap();
+        assert_eq!(snapshot.num_gates(), 2);
+        assert_eq!(snapshot.output_id(), CellId(5));
+
+        // builder is still usable after the snapshot
+        let _ = builder.add_addition(out_1, prv_refs[0]).unwrap();
+        let circ = builder.build().unwrap();
+        assert_eq!(circ.num_gates(), 3);
+    }
+
+    #[test]
+    fn test_input_config() {
+        let config = simple_circuit().input_config();
+        assert_eq!(config.n_pub(), 2);
+        assert_eq!(config.n_priv(), 1);
+    }
+
+    #[test]
+    fn test_name_input() {
+        let mut builder = CircuitBuilder::new(InputConfig::new(2, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let out = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
+        builder.name_input(prv_refs[0], "secret").unwrap();
+        builder.name_input(pb_refs[1], "scale").unwrap();
+
+        let error = builder.name_input(pb_refs[0], "secret").unwrap_err();
+        assert_eq!(format!("{}", error), "Name secret is already used.");
+        let error = builder.name_input(out, "out").unwrap_err();
+        assert_eq!(format!("{}", error), "Wire(2) is not an input.");
+        let error = builder.name_input(Cellref::Input(4), "x").unwrap_err();
+        assert_eq!(format!("{}", error), "Input 4 does not exist.");
+
+        let circ = builder.build().unwrap();
+        assert_eq!(circ.input_number("secret"), Some(2));
+        assert_eq!(circ.input_number("scale"), Some(1));
+        assert_eq!(circ.input_number("out"), None);
+    }
+
+    #[test]
+    fn test_specialize() {
+        // pub_1 = 5 becomes a constant cell, leaving pub_0 and priv_0
+        let circ = simple_circuit();
+        let specialized = circ.specialize(&[(InputIndex(1), Fr::from(5))]).unwrap();
+        assert_eq!(specialized.input_config(), InputConfig::new(1, 1));
+        assert_eq!(specialized.constants, vec![Constant::from(5)]);
+        assert_eq!(specialized.n_cells(), circ.n_cells());
+        let (public, private) = ([Fr::from(3)], [Fr::from(7)]);
+        assert_eq!(
+            specialized.compute_output(&public, &private).unwrap(),
+            Fr::from(57)
+        );
+
+        // fixing to an existing constant shares its cell
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let two = builder.add_constant(2);
+        let sum = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
+        builder.add_multiplication(sum, two).unwrap();
+        builder.name_input(prv_refs[0], "x").unwrap();
+        let circ = builder.build().unwrap();
+        let specialized = circ.specialize(&[(InputIndex(0), Fr::from(2))]).unwrap();
+        assert_eq!(specialized.constants, vec![Constant::from(2)]);
+        assert_eq!(specialized.n_cells(), circ.n_cells() - 1);
+        assert_eq!(specialized.input_number("x"), Some(0));
+        assert_eq!(
+            specialized.compute_output(&[], &[Fr::from(3)]).unwrap(),
+            Fr::from(10)
+        );
+
+        let error = circ
+            .specialize(&[(InputIndex(2), Fr::from(1))])
+            .unwrap_err();
+        assert_eq!(format!("{}", error), "Input 2 does not exist.");
+        let fixed = [(InputIndex(0), Fr::from(1)), (InputIndex(0), Fr::from(2))];
+        let error = circ.specialize(&fixed).unwrap_err();
+        assert_eq!(format!("{}", error), "Input 0 is fixed more than once.");
+
+        // values beyond u64 are fixed as they are
+        let circ = simple_circuit();
+        let specialized = circ.specialize(&[(InputIndex(1), -Fr::from(5))]).unwrap();
+        assert_eq!(
+            specialized.constants,
+            vec![Constant::from_field(-Fr::from(5))]
+        );
+        assert_eq!(
+            specialized.compute_output(&public, &private).unwrap(),
+            -Fr::from(43)
+        );
+
+        // an input wired to a constant can only be fixed to its value
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 0));
+        let (pb_refs, _) = builder.get_input_refs();
+        builder.add_addition(pb_refs[0], pb_refs[0]).unwrap();
+        builder.pad_inputs_to(1, 1).unwrap();
+        let circ = builder.build().unwrap();
+        assert!(circ.specialize(&[(InputIndex(1), Fr::from(0))]).is_ok());
+        let error = circ
+            .specialize(&[(InputIndex(1), Fr::from(1))])
+            .unwrap_err();
+        assert_eq!(
+            format!("{}", error),
+            "Fixed inputs wire constant 0 to constant 1."
+        );
+    }
+
+    #[test]
+    fn test_check_domain_feasible() {
+        // Fq has two-adicity 1, but mixed radix domains reach up to 16 cells
+        assert!(simple_circuit().check_domain_feasible::<Fq>().is_ok());
+
+        // 10 rows and 2 inputs need a domain of 32
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let mut out = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
+        for _ in 1..10 {
+            out = builder.add_addition(out, prv_refs[0]).unwrap();
+        }
+        let circ = builder.build().unwrap();
+
+        assert!(circ.check_domain_feasible::<Fr>().is_ok());
+        let error = circ.check_domain_feasible::<Fq>().unwrap_err();
+        assert_eq!(
+            format!("{}", error),
+            "Domain of size 32 is not supported by the field of two-adicity 1."
+        );
+    }
+
+    #[test]
+    fn test_row_cells() {
+        let circ = simple_circuit();
+        let cells = |ids: [usize; 3]| Some((CellId(ids[0]), CellId(ids[1]), CellId(ids[2])));
+        assert_eq!(circ.row_cells(RowIndex(1)), cells([3, 4, 5]));
+        assert_eq!(
+            circ.row_cells(RowIndex(2)),
+            cells([6, 7, circ.output_id().0])
+        );
+        assert_eq!(circ.row_cells(RowIndex(3)), None);
+    }
+
+    #[test]
+    fn test_index_conversions() {
+        let circ = simple_circuit();
+        let n_cells = circ.n_cells();
+
+        assert_eq!(CellId(7).row(), RowIndex(2));
+        assert_eq!(RowIndex(2).cells(), [CellId(6), CellId(7), CellId(8)]);
+        assert_eq!(InputIndex(0).cell(n_cells), CellId(n_cells - 1));
+        assert_eq!(CellId(n_cells - 3).input_index(n_cells), InputIndex(2));
+        assert_eq!(circ.input_cell(InputIndex(2)), Some(CellId(n_cells - 3)));
+        assert_eq!(circ.input_cell(InputIndex(3)), None);
+
+        assert_eq!(usize::from(CellId(5)), 5);
+        assert_eq!(RowIndex::from(5), RowIndex(5));
+        assert_eq!(usize::from(InputIndex::from(5)), 5);
+    }
+
+    #[test]
+    fn test_input_cell_ids() {
+        let circ = simple_circuit();
+        let ids = circ.input_cell_ids().collect::<Vec<_>>();
+        assert_eq!(ids, [CellId(9), CellId(10), CellId(11)]);
+        assert_eq!(ids.last(), Some(&CellId(circ.n_cells() - 1)));
+    }
+
+    #[test]
+    fn test_is_satisfiable() {
+        // pub_0 / priv_0 = pub_1
+        let mut builder = CircuitBuilder::new(InputConfig::new(2, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let out = builder.add_division(pb_refs[0], prv_refs[0]).unwrap();
+        builder.assert_equals_public(out, 1).unwrap();
+        let circ = builder.build().unwrap();
+
+        let public = [Fr::from(10), Fr::from(2)];
+        assert!(circ.is_satisfiable(&public, &[Fr::from(5)]));
+        assert!(!circ.is_satisfiable(&[Fr::from(10), Fr::from(3)], &[Fr::from(5)]));
+        assert!(!circ.is_satisfiable(&public, &[Fr::from(0)]));
+        assert!(!circ.is_satisfiable(&public, &[]));
+    }
+
+    #[test]
+    fn test_assert_eq_wires() {
+        let mut builder = CircuitBuilder::new(InputConfig::new(0, 2));
+        let (_, prv_refs) = builder.get_input_refs();
+        let (a, b) = (prv_refs[0], prv_refs[1]);
+        let x = builder.add_addition(a, b).unwrap();
+        let y = builder.add_addition(b, a).unwrap();
+        builder.assert_eq_wires(x, y).unwrap();
+        let z = builder.add_multiplication(a, b).unwrap();
+        builder.assert_eq_wires(x, z).unwrap();
+        assert!(builder.assert_eq_wires(x, Cellref::Wire(12)).is_err());
+        let circ = builder.build().unwrap();
+
+        // a + b = b + a always holds, a + b = a * b only for (2, 2) among these
+        assert!(circ.is_satisfiable::<Fr>(&[], &[Fr::from(2), Fr::from(2)]));
+        assert!(!circ.is_satisfiable::<Fr>(&[], &[Fr::from(2), Fr::from(3)]));
+        assert!(circ.solve_witness(&[Fr::from(2), Fr::from(3)]).is_err());
+    }
+
+    #[test]
+    fn test_gates() {
+        let gates = simple_circuit().gates().collect::<Vec<_>>();
+        let expected = [(Op::Add, 0), (Op::Mul, 3), (Op::Add, 6)]
+            .into_iter()
+            .enumerate()
+            .map(|(row, (op, id))| GateView {
+                row,
+                op,
+                lhs: id,
+                rhs: id + 1,
+                out: id + 2,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(gates, expected);
+    }
+
+    #[test]
+    fn test_wiring_statistics() {
+        let circ = simple_circuit();
+
+        // [1, 7, 9] is the largest class, output cell 8 is not wired
+        assert_eq!(circ.max_fan_out(), 3);
+        assert_eq!(circ.total_wired_cells(), 11);
+    }
+
+    #[test]
+    fn test_selector_is_constant() {
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let out = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
+        assert_eq!(
+            builder.snapshot().unwrap().selector_is_constant(),
+            Some(Op::Add)
+        );
+
+        let _ = builder.add_multiplication(out, prv_refs[0]).unwrap();
+        assert_eq!(builder.build().unwrap().selector_is_constant(), None);
+    }
+
+    #[test]
+    fn test_wire_constraint_invalid_refs() {
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 0));
+        let out = builder
+            .add_addition(Cellref::Input(1), Cellref::Input(1))
+            .unwrap();
+
+        let error = builder
+            .add_wire_constraint(Cellref::Input(2), out)
+            .unwrap_err();
+        assert_eq!(format!("{:#}", error), "X: Input 2 does not exist.");
+        let error = builder
+            .add_wire_constraint(out, Cellref::Wire(3))
+            .unwrap_err();
+        assert_eq!(format!("{:#}", error), "Y: Wire 3 does not exist.");
+        assert_eq!(
+            error.downcast_ref::<CircuitBuildError>(),
+            Some(&CircuitBuildError::InvalidWireRef(3))
+        );
+
+        // invalid pairs are not added
+        builder.add_wire_constraint(Cellref::Input(1), out).unwrap();
+        assert_eq!(builder.wiring_pairs.len(), 3);
+    }
+
     #[test]
     fn test_lhs_invalid_input_ref() {
         let mut builder = CircuitBuilder::new(InputConfig::new(1, 0));
 
         let res = builder.add_addition(Cellref::Input(100), Cellref::Input(1));
         let error = res.unwrap_err();
-        assert_eq!(format!("{}", error), "LHS: Input 100 does not exist.");
+        assert_eq!(format!("{:#}", error), "LHS: Input 100 does not exist.");
+        assert_eq!(
+            error.downcast_ref::<CircuitBuildError>(),
+            Some(&CircuitBuildError::InvalidInputRef(100))
+        );
     }
 
     #[test]
@@ -339,7 +2158,7 @@ mod tests {
 
         let res = builder.add_addition(Cellref::Input(1), Cellref::Input(100));
         let error = res.unwrap_err();
-        assert_eq!(format!("{}", error), "RHS: Input 100 does not exist.");
+        assert_eq!(format!("{:#}", error), "RHS: Input 100 does not exist.");
     }
 
     #[test]
@@ -348,7 +2167,7 @@ mod tests {
 
         let res = builder.add_addition(Cellref::Wire(1), Cellref::Input(1));
         let error = res.unwrap_err();
-        assert_eq!(format!("{}", error), "LHS: Wire 1 does not exist.");
+        assert_eq!(format!("{:#}", error), "LHS: Wire 1 does not exist.");
     }
 
     #[test]
@@ -357,6 +2176,210 @@ mod tests {
 
         let res = builder.add_addition(Cellref::Input(1), Cellref::Wire(1));
         let error = res.unwrap_err();
-        assert_eq!(format!("{}", error), "RHS: Wire 1 does not exist.");
+        assert_eq!(format!("{:#}", error), "RHS: Wire 1 does not exist.");
+    }
+
+    #[test]
+    fn test_assert_equals_invalid_public_index() {
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+        let (_, prv_refs) = builder.get_input_refs();
+        let out = builder
+            .add_multiplication(prv_refs[0], prv_refs[0])
+            .unwrap();
+
+        let res = builder.assert_equals_public(out, 1);
+        let error = res.unwrap_err();
+        assert_eq!(format!("{}", error), "Public input 1 does not exist.");
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let circ = simple_circuit();
+
+        let dot = circ.to_dot();
+        assert!(dot.starts_with("digraph circuit {"));
+        assert_eq!(dot.matches("shape=box").count(), 3);
+        assert_eq!(dot.matches("[label=\"Add\"").count(), 2);
+        assert_eq!(dot.matches("[label=\"Mul\"").count(), 1);
+        assert_eq!(dot.matches(" -> ").count(), 6);
+        assert!(dot.contains("input_0 -> gate_0 [label=\"lhs\"];"));
+        assert!(dot.contains("input_2 -> gate_0 [label=\"rhs\"];"));
+        assert!(dot.contains("gate_0 -> gate_1 [label=\"lhs\"];"));
+        assert!(dot.contains("input_2 -> gate_2 [label=\"rhs\"];"));
+    }
+
+    #[test]
+    fn test_build_without_gates() {
+        let builder = CircuitBuilder::new(InputConfig::new(1, 0));
+        assert_eq!(builder.build().err(), Some(CircuitBuildError::NoGates));
+    }
+
+    #[test]
+    fn test_validate_invariants() {
+        let circ = simple_circuit();
+        assert!(circ.validate_invariants().is_ok());
+
+        // a row without selector
+        let inconsistent = Circuit {
+            selectors: vec![Op::Add, Op::Mul],
+            ..circ
+        };
+        assert_eq!(
+            inconsistent.validate_invariants(),
+            Err(CircuitBuildError::SelectorCountMismatch(2, 3))
+        );
+    }
+
+    #[test]
+    fn test_validate_permutation() {
+        let circ = simple_circuit();
+        assert!(circ.validate_permutation().is_ok());
+
+        // cell 3 in two classes is the image of both 0 and 5
+        let broken = Circuit {
+            copy_constraints: vec![vec![0, 3], vec![3, 5]],
+            ..circ.clone()
+        };
+        let error = broken.validate_permutation().unwrap_err();
+        assert_eq!(
+            format!("{}", error),
+            "Cell 3 appears more than once in the permutation."
+        );
+
+        let broken = Circuit {
+            copy_constraints: vec![vec![0, 100]],
+            ..circ
+        };
+        let error = broken.validate_permutation().unwrap_err();
+        assert_eq!(format!("{}", error), "Cell 100 does not exist.");
+    }
+
+    #[test]
+    fn test_add_scalar_multiplication() {
+        // out = 3 * pub_0
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 0));
+        let (pb_refs, _) = builder.get_input_refs();
+        let out = builder.add_scalar_multiplication(pb_refs[0], 3).unwrap();
+        assert_eq!(out, Cellref::Wire(2));
+        let circ = builder.build().unwrap();
+
+        assert_eq!(circ.n_inputs(), 1);
+        assert_eq!(circ.constants, vec![Constant::from(3)]);
+        assert_eq!(
+            circ.compute_output(&[Fr::from(5)], &[]).unwrap(),
+            Fr::from(15)
+        );
+    }
+
+    #[test]
+    fn test_depth() {
+        // add -> mul -> add
+        assert_eq!(simple_circuit().depth().unwrap(), 3);
+
+        // both additions are evaluated in the first round
+        let mut builder = CircuitBuilder::new(InputConfig::new(2, 0));
+        let (pb_refs, _) = builder.get_input_refs();
+        let sum = builder.add_addition(pb_refs[0], pb_refs[1]).unwrap();
+        let double = builder.add_addition(pb_refs[0], pb_refs[0]).unwrap();
+        builder.add_multiplication(sum, double).unwrap();
+        assert_eq!(builder.build().unwrap().depth().unwrap(), 2);
+
+        // inverse of pub_1 is solved backward, then multiplied and added
+        let mut builder = CircuitBuilder::new(InputConfig::new(2, 0));
+        let (pb_refs, _) = builder.get_input_refs();
+        let quotient = builder.add_division(pb_refs[0], pb_refs[1]).unwrap();
+        builder.add_addition(quotient, pb_refs[0]).unwrap();
+        assert_eq!(builder.build().unwrap().depth().unwrap(), 3);
+
+        // lhs of the gate is wired to its own output
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 0));
+        let (pb_refs, _) = builder.get_input_refs();
+        builder.add_addition(pb_refs[0], pb_refs[0]).unwrap();
+        let mut circ = builder.build().unwrap();
+        circ.copy_constraints = vec![vec![0, 2], vec![1, 3]];
+        let error = circ.depth().unwrap_err();
+        assert_eq!(
+            format!("{}", error),
+            "Output cannot be computed from the inputs."
+        );
+    }
+
+    #[test]
+    fn test_pad_inputs_to() {
+        // out = priv_0 * priv_0, padded to 1 public and 2 private inputs
+        let mut builder = CircuitBuilder::new(InputConfig::new(0, 1));
+        let (_, prv_refs) = builder.get_input_refs();
+        builder
+            .add_multiplication(prv_refs[0], prv_refs[0])
+            .unwrap();
+        builder.pad_inputs_to(1, 2).unwrap();
+        let error = builder.pad_inputs_to(0, 2).unwrap_err();
+        assert_eq!(
+            format!("{}", error),
+            "Cannot pad 1 public and 2 private inputs to 0 and 2."
+        );
+        let circ = builder.build().unwrap();
+        assert_eq!(circ.input_config(), InputConfig::new(1, 2));
+
+        // the private input moved to input 1, padding inputs 0 and 2 are zero
+        let inputs = [Fr::from(0), Fr::from(3), Fr::from(0)];
+        let witness = circ.solve_witness(&inputs).unwrap();
+        assert_eq!(witness[2], Fr::from(9));
+        let n_cells = circ.n_cells();
+        assert_eq!(witness[n_cells - 1], Fr::from(0));
+        assert_eq!(witness[n_cells - 3], Fr::from(0));
+
+        let inputs = [Fr::from(0), Fr::from(3), Fr::from(1)];
+        assert!(circ.solve_witness(&inputs).is_err());
+    }
+
+    #[test]
+    fn test_constant_round_trips_field_elements() {
+        assert_eq!(
+            Constant::from_field(-Fr::from(1)).to_field::<Fr>(),
+            -Fr::from(1)
+        );
+        assert_eq!(
+            Constant::from_field(-Fq::from(1)).to_field::<Fq>(),
+            -Fq::from(1)
+        );
+        assert_eq!(Constant::from(7).to_field::<Fq>(), Fq::from(7));
+
+        // a constant above the modulus of Fr is reduced into it
+        let constant = Constant::from_field(-Fq::from(1));
+        assert_eq!(
+            constant.to_field::<Fr>(),
+            Fr::from(BigUint::from(Fq::MODULUS)) - Fr::from(1)
+        );
+    }
+
+    #[test]
+    fn test_build_conflicting_constants() {
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 0));
+        let (pb_refs, _) = builder.get_input_refs();
+        let zero = builder.add_constant(0);
+        let one = builder.add_constant(1);
+        let _ = builder.add_addition(pb_refs[0], pb_refs[0]).unwrap();
+        builder.add_wire_constraint(pb_refs[0], zero).unwrap();
+        builder.add_wire_constraint(pb_refs[0], one).unwrap();
+
+        assert_eq!(
+            builder.build().err(),
+            Some(CircuitBuildError::ConflictingConstraint(pb_refs[0], one))
+        );
+    }
+
+    #[test]
+    fn test_input_vanishing_polynomial() {
+        let circ = simple_circuit();
+        let poly = circ.input_vanishing_polynomial::<Fr>().unwrap();
+        let domain = GeneralEvaluationDomain::<Fr>::new(16).unwrap();
+
+        // public inputs are at cells 11 and 10, private input at 9
+        assert_eq!(poly.degree(), 2);
+        for id in [10, 11] {
+            assert_eq!(poly.evaluate(&domain.element(id)), Fr::from(0));
+        }
+        assert_ne!(poly.evaluate(&domain.element(9)), Fr::from(0));
     }
 }
@@ -1,15 +1,20 @@
-use ark_ff::{FftField, PrimeField};
+use ark_ff::{BigInteger, FftField, Field, PrimeField, Zero};
 use ark_poly::{
-    univariate::DensePolynomial, EvaluationDomain, Evaluations, GeneralEvaluationDomain,
+    univariate::{DenseOrSparsePolynomial, DensePolynomial},
+    DenseUVPolynomial, EvaluationDomain, Evaluations, GeneralEvaluationDomain, Polynomial,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use ark_crypto_primitives::sponge::poseidon::{find_poseidon_ark_and_mds, PoseidonConfig};
+use ark_serialize::Compress;
 
-use crate::{circuit::Op, Circuit};
+use crate::{circuit::Id, Circuit};
 
 ///
 /// compute inputs polynomial
 /// this can be done in setup phase
+/// Prover uses `PublicInputEncoder`, this is kept as the reference to test it against.
+#[cfg(test)]
 pub(crate) fn compute_public_input_polynomial<F>(
     circ: &Circuit,
     public_input: &[F],
@@ -24,6 +29,11 @@ where
     let mut pad = vec![F::zero(); n_cells - evals.len()];
     pad.append(&mut evals.iter().rev().copied().collect::<Vec<_>>());
 
+    // constant cells are known to the verifier as well as public inputs
+    for (id, value) in circ.constant_cells() {
+        pad[id] = value.to_field();
+    }
+
     let domain = GeneralEvaluationDomain::<F>::new(domain_size).unwrap();
     let evaluations = Evaluations::from_vec_and_domain(pad, domain);
     let poly = evaluations.interpolate();
@@ -31,30 +41,64 @@ where
     Ok(poly)
 }
 
+/// Encoder of public inputs into the public input polynomial of a circuit.
+/// The domain and the evaluations of constant cells are computed once per circuit,
+/// so encoding only places the public inputs and interpolates.
+#[derive(Clone, Debug)]
+pub(crate) struct PublicInputEncoder<F: FftField> {
+    domain: GeneralEvaluationDomain<F>,
+    /// Evaluations with constant cells filled and public input cells left zero.
+    layout: Vec<F>,
+}
+
+impl<F: FftField> PublicInputEncoder<F> {
+    pub(crate) fn new(circ: &Circuit) -> Result<Self> {
+        let domain_size = circ.n_cells().checked_next_power_of_two().unwrap();
+        let domain = GeneralEvaluationDomain::<F>::new(domain_size)
+            .ok_or(anyhow!("Domain cannot be constructed from circuit size"))?;
+
+        let mut layout = vec![F::zero(); circ.n_cells()];
+        for (id, value) in circ.constant_cells() {
+            layout[id] = value.to_field();
+        }
+
+        Ok(Self { domain, layout })
+    }
+
+    /// Compute the public input polynomial.
+    /// `public_input` has to hold as many values as the public inputs of the circuit.
+    pub(crate) fn encode(&self, public_input: &[F]) -> DensePolynomial<F> {
+        // public input i is placed at cell n_cells - (i + 1)
+        let mut evals = self.layout.clone();
+        for (cell, value) in evals.iter_mut().rev().zip(public_input) {
+            *cell = *value;
+        }
+
+        Evaluations::from_vec_and_domain(evals, self.domain).interpolate()
+    }
+}
+
 /// compute selector polynomial independent of inputs
 /// this can be done in setup phase
 pub(crate) fn compute_selector_polynomial<F>(circ: &Circuit) -> Result<DensePolynomial<F>>
 where
     F: FftField,
 {
+    // builder rejects circuits without gates, but a circuit may come from elsewhere
+    if circ.num_gates() == 0 {
+        return Err(anyhow!("Circuit has no gates."));
+    }
+
     // compute selector polynomial from circuit
     let domain_size = circ.n_cells().checked_next_power_of_two().unwrap();
 
-    let selectors = circ
-        .selectors
+    // S is 1 on the first cell of addition gates and 0 elsewhere,
+    // which the builder keeps track of as gates are added
+    let evals = circ
+        .selector_evals
         .iter()
-        .map(|op| match op {
-            Op::Add => F::ONE,
-            Op::Mul => F::ZERO,
-        })
-        .collect::<Vec<_>>();
-
-    let mut evals = vec![selectors[0]];
-    selectors.iter().skip(1).for_each(|v| {
-        evals.push(F::ZERO);
-        evals.push(F::ZERO);
-        evals.push(*v);
-    });
+        .map(|s| if *s { F::ONE } else { F::ZERO })
+        .collect();
 
     let domain = GeneralEvaluationDomain::<F>::new(domain_size).unwrap();
     let evaluations = Evaluations::from_vec_and_domain(evals, domain);
@@ -62,8 +106,242 @@ where
     Ok(poly)
 }
 
-// polynomial which implements rotation like followings
-// W(𝜔-2 , 𝜔1 , 𝜔3) = (𝜔1 , 𝜔3 , 𝜔-2 ) , W(𝜔-1 , 𝜔0) = (𝜔0 , 𝜔-1), ,,,
-fn compute_wire_rotation_polynomial() {
-    todo!()
+/// compute polynomial which implements rotation like followings
+/// W(𝜔-2 , 𝜔1 , 𝜔3) = (𝜔1 , 𝜔3 , 𝜔-2 ) , W(𝜔-1 , 𝜔0) = (𝜔0 , 𝜔-1), ,,,
+/// Each copy constraint class is rotated to the next cell in the class.
+/// Cells without copy constraints and padding are mapped to themselves.
+/// this can be done in setup phase
+pub(crate) fn compute_wire_rotation_polynomial<F>(circ: &Circuit) -> Result<DensePolynomial<F>>
+where
+    F: FftField,
+{
+    let domain_size = circ.n_cells().checked_next_power_of_two().unwrap();
+    let domain = GeneralEvaluationDomain::<F>::new(domain_size)
+        .ok_or(anyhow!("Domain cannot be constructed from circuit size"))?;
+
+    let mut rotation = (0..domain.size()).collect::<Vec<_>>();
+    circ.copy_constraints.iter().for_each(|class| {
+        class.iter().enumerate().for_each(|(i, id)| {
+            rotation[*id] = class[(i + 1) % class.len()];
+        })
+    });
+
+    let evals = rotation.iter().map(|i| domain.element(*i)).collect();
+    let evaluations = Evaluations::from_vec_and_domain(evals, domain);
+    Ok(evaluations.interpolate())
+}
+
+/// compute polynomial which vanishes on given points.
+/// Z(X) = ∏(X - point)
+pub(crate) fn compute_vanishing_polynomial<F>(
+    points: impl IntoIterator<Item = F>,
+) -> DensePolynomial<F>
+where
+    F: FftField,
+{
+    points.into_iter().fold(
+        DensePolynomial::from_coefficients_vec(vec![F::ONE]),
+        |acc, point| &acc * &DensePolynomial::from_coefficients_vec(vec![-point, F::ONE]),
+    )
+}
+
+/// Evaluate `poly` at each of `points`.
+pub fn batch_evaluate<F: Field>(poly: &DensePolynomial<F>, points: &[F]) -> Vec<F> {
+    points.iter().map(|point| poly.evaluate(point)).collect()
+}
+
+/// compute the polynomial of degree < n through n pairs of `points` and `values`
+/// by Lagrange interpolation, I(X) = Σ y_i⋅∏_{j≠i} (X - x_j) / (x_i - x_j).
+pub(crate) fn interpolate<F: FftField>(points: &[F], values: &[F]) -> Result<DensePolynomial<F>> {
+    let mut poly = DensePolynomial::zero();
+    for (i, (x_i, y_i)) in points.iter().zip(values).enumerate() {
+        let others = points
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(_, x_j)| *x_j);
+        let den = others
+            .clone()
+            .map(|x_j| *x_i - x_j)
+            .product::<F>()
+            .inverse()
+            .ok_or(anyhow!("Points should be distinct."))?;
+        poly += &(&compute_vanishing_polynomial(others) * (*y_i * den));
+    }
+    Ok(poly)
+}
+
+/// compute polynomial which vanishes on the domain points of given cells.
+/// Z(X) = ∏(X - 𝜔^id)
+pub(crate) fn vanishing_over_cells<F>(
+    domain: GeneralEvaluationDomain<F>,
+    cell_ids: &[Id],
+) -> DensePolynomial<F>
+where
+    F: FftField,
+{
+    compute_vanishing_polynomial(cell_ids.iter().map(|id| domain.element(*id)))
+}
+
+/// compute P(shift⋅X) by scaling the i-th coefficient by shift^i.
+/// With shift 𝜔 this is the rotation of P's evaluations over the domain, without any FFT.
+pub(crate) fn shift_polynomial<F>(poly: &DensePolynomial<F>, shift: F) -> DensePolynomial<F>
+where
+    F: FftField,
+{
+    let mut power = F::ONE;
+    let coeffs = poly
+        .coeffs
+        .iter()
+        .map(|c| {
+            let scaled = *c * power;
+            power *= shift;
+            scaled
+        })
+        .collect();
+    DensePolynomial::from_coefficients_vec(coeffs)
+}
+
+/// divide polynomial by vanishing polynomial.
+/// Returns error if the polynomial does not vanish on all the roots of the vanishing polynomial.
+pub(crate) fn compute_quotient_polynomial<F>(
+    poly: &DensePolynomial<F>,
+    vanishing_poly: &DensePolynomial<F>,
+) -> Result<DensePolynomial<F>>
+where
+    F: FftField,
+{
+    let (q, r) = DenseOrSparsePolynomial::from(poly)
+        .divide_with_q_and_r(&DenseOrSparsePolynomial::from(vanishing_poly))
+        .ok_or(anyhow!("Vanishing polynomial should not be zero"))?;
+
+    if !r.is_zero() {
+        return Err(anyhow!("Polynomial does not vanish on the given points"));
+    }
+
+    Ok(q)
+}
+
+/// Big-endian bytes of `f`, as JS bigints and Solidity uint256 are usually encoded.
+/// arkworks' canonical serialization is little-endian, so the two differ byte-wise.
+/// The length is the byte size of the field's big integer, 32 for Fr.
+pub fn field_to_be_bytes<F: PrimeField>(f: &F) -> Vec<u8> {
+    f.into_bigint().to_bytes_be()
+}
+
+/// Field element from big-endian `bytes` written by `field_to_be_bytes`.
+/// Returns error if the length differs or the value is not smaller than the modulus.
+pub fn field_from_be_bytes<F: PrimeField>(bytes: &[u8]) -> Result<F> {
+    // canonical form is the same bytes in little-endian order
+    let le_bytes = bytes.iter().rev().copied().collect::<Vec<_>>();
+    if le_bytes.len() != F::zero().serialized_size(Compress::No) {
+        return Err(anyhow!(
+            "Expected {} bytes, got {}.",
+            F::zero().serialized_size(Compress::No),
+            bytes.len()
+        ));
+    }
+    F::deserialize_uncompressed(&le_bytes[..])
+        .map_err(|_| anyhow!("Bytes are not a canonical field element."))
+}
+
+/// Poseidon parameters with rate 2, capacity 1, alpha 17, 8 full rounds and 31 partial rounds.
+/// Round constants and MDS matrix are derived deterministically from the field, so prover
+/// and verifier building them separately get the same sponge.
+pub fn default_poseidon_config<F: PrimeField>() -> PoseidonConfig<F> {
+    let (full_rounds, partial_rounds, alpha, rate) = (8, 31, 17, 2);
+    let (ark, mds) = find_poseidon_ark_and_mds::<F>(
+        F::MODULUS_BIT_SIZE as u64,
+        rate,
+        full_rounds,
+        partial_rounds,
+        0,
+    );
+
+    PoseidonConfig::new(
+        full_rounds as usize,
+        partial_rounds as usize,
+        alpha,
+        mds,
+        ark,
+        rate,
+        1,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::{CircuitBuilder, InputConfig};
+    use ark_bls12_381::Fr;
+    use ark_ff::UniformRand;
+    use ark_serialize::CanonicalSerialize;
+
+    #[test]
+    fn test_vanishing_over_cells() {
+        let domain = GeneralEvaluationDomain::<Fr>::new(16).unwrap();
+        let cells = [0, 3, 6, 15];
+
+        let poly = vanishing_over_cells(domain, &cells);
+        assert_eq!(poly.degree(), cells.len());
+        for (id, x) in domain.elements().enumerate() {
+            assert_eq!(poly.evaluate(&x) == Fr::from(0), cells.contains(&id));
+        }
+    }
+
+    #[test]
+    fn test_shift_polynomial() {
+        let mut rng = ark_std::test_rng();
+        let domain = GeneralEvaluationDomain::<Fr>::new(16).unwrap();
+        let omega = domain.group_gen();
+        let poly = DensePolynomial::<Fr>::rand(15, &mut rng);
+
+        let shifted = shift_polynomial(&poly, omega);
+        for _ in 0..4 {
+            let x = Fr::rand(&mut rng);
+            assert_eq!(shifted.evaluate(&x), poly.evaluate(&(omega * x)));
+        }
+    }
+
+    #[test]
+    fn test_public_input_encoder() {
+        let mut builder = CircuitBuilder::new(InputConfig::new(2, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let out = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
+        let two = builder.add_constant(2);
+        let _ = builder.add_multiplication(out, two).unwrap();
+        let circ = builder.build().unwrap();
+
+        let encoder = PublicInputEncoder::new(&circ).unwrap();
+        for public_input in [[Fr::from(3), Fr::from(5)], [Fr::from(4), Fr::from(-1)]] {
+            assert_eq!(
+                encoder.encode(&public_input),
+                compute_public_input_polynomial(&circ, &public_input).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_field_be_bytes() {
+        let mut rng = ark_std::test_rng();
+        let x = Fr::rand(&mut rng);
+        assert_eq!(
+            field_from_be_bytes::<Fr>(&field_to_be_bytes(&x)).unwrap(),
+            x
+        );
+
+        // 258 = 0x0102 is at the end, unlike the little-endian canonical form
+        let bytes = field_to_be_bytes(&Fr::from(258));
+        let mut expected = vec![0u8; 30];
+        expected.extend([1, 2]);
+        assert_eq!(bytes, expected);
+
+        let mut canonical = vec![];
+        Fr::from(258).serialize_compressed(&mut canonical).unwrap();
+        assert_eq!(canonical[..2], [2, 1]);
+
+        assert!(field_from_be_bytes::<Fr>(&bytes[1..]).is_err());
+        let modulus = Fr::MODULUS.to_bytes_be();
+        assert!(field_from_be_bytes::<Fr>(&modulus).is_err());
+    }
 }
@@ -0,0 +1,96 @@
+use anyhow::{anyhow, Result};
+use ark_bls12_381::Fr;
+use ark_ff::{Field, Fp2, Fp2Config, MontFp, Zero};
+use ark_poly::univariate::{DenseOrSparsePolynomial, DensePolynomial};
+use ark_poly::DenseUVPolynomial;
+
+use crate::types::UniPoly381;
+
+/// Quadratic extension Fr[u]/(u^2 - 7).
+/// 7 generates the multiplicative group of Fr, so it is not a square.
+pub struct FrQuadConfig;
+
+impl Fp2Config for FrQuadConfig {
+    type Fp = Fr;
+
+    const NONRESIDUE: Fr = MontFp!("7");
+
+    const FROBENIUS_COEFF_FP2_C1: &'static [Fr] = &[Fr::ONE, MontFp!("-1")];
+}
+
+/// Degree-2 extension of the scalar field.
+pub type Fr2 = Fp2<FrQuadConfig>;
+
+/// Minimal polynomial of `x` over Fr, m(X) = X^2 - (x + x̄)⋅X + x⋅x̄.
+pub(crate) fn minimal_polynomial(x: Fr2) -> UniPoly381 {
+    DensePolynomial::from_coefficients_vec(vec![x.norm(), -x.c0.double(), Fr::ONE])
+}
+
+/// Divide `poly` by the minimal polynomial of `x`.
+/// Returns the quotient and P(x), which is the remainder s0 + s1⋅X evaluated at x.
+pub(crate) fn divide_by_minimal_polynomial(poly: &UniPoly381, x: Fr2) -> Result<(UniPoly381, Fr2)> {
+    if x.c1.is_zero() {
+        return Err(anyhow!("Extension point should not lie in the base field."));
+    }
+
+    let (q, r) = DenseOrSparsePolynomial::from(poly)
+        .divide_with_q_and_r(&DenseOrSparsePolynomial::from(minimal_polynomial(x)))
+        .ok_or(anyhow!("Minimal polynomial should not be zero"))?;
+    let (s0, s1) = (coeff(&r, 0), coeff(&r, 1));
+
+    Ok((q, Fr2::new(s0 + s1 * x.c0, s1 * x.c1)))
+}
+
+/// Recover the remainder (s0, s1) from P(x) = s0 + s1⋅x.
+/// Returns `None` if `x` lies in the base field.
+pub(crate) fn remainder_from_value(x: Fr2, value: Fr2) -> Option<(Fr, Fr)> {
+    let s1 = value.c1 * x.c1.inverse()?;
+    Some((value.c0 - s1 * x.c0, s1))
+}
+
+fn coeff(poly: &UniPoly381, i: usize) -> Fr {
+    poly.coeffs.get(i).copied().unwrap_or(Fr::zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::{LegendreSymbol, UniformRand};
+    use ark_poly::Polynomial;
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_nonresidue_is_not_square() {
+        assert_eq!(
+            FrQuadConfig::NONRESIDUE.legendre(),
+            LegendreSymbol::QuadraticNonResidue
+        );
+    }
+
+    #[test]
+    fn test_divide_by_minimal_polynomial() {
+        let mut rng = test_rng();
+        let poly = UniPoly381::rand(10, &mut rng);
+        let x = Fr2::rand(&mut rng);
+
+        let (q, value) = divide_by_minimal_polynomial(&poly, x).unwrap();
+
+        // P(x) computed over the extension
+        let expected = poly.coeffs.iter().rev().fold(Fr2::zero(), |acc, c| {
+            acc * x + Fr2::from_base_prime_field(*c)
+        });
+        assert_eq!(value, expected);
+
+        // P(z) = Q(z)⋅m(z) + s0 + s1⋅z on a base field point
+        let (s0, s1) = remainder_from_value(x, value).unwrap();
+        let z = Fr::rand(&mut rng);
+        assert_eq!(
+            poly.evaluate(&z),
+            q.evaluate(&z) * minimal_polynomial(x).evaluate(&z) + s0 + s1 * z
+        );
+
+        assert!(
+            divide_by_minimal_polynomial(&poly, Fr2::from_base_prime_field(Fr::from(3))).is_err()
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::{anyhow, Result};
+use ark_ff::Field;
+
+use crate::circuit::Op;
+
+/// Operand of a row of a gate layout.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GateOperand {
+    /// Index of an input given to `CircuitBuilder::add_gate_layout`.
+    Input(usize),
+    /// Output of an earlier row of the gate.
+    Row(usize),
+}
+
+/// Reusable layout of rows of additions and multiplications, defined by the user.
+///
+/// The gate constraint S⋅[a + b] + (1 – S)⋅a⋅b − c has a single selector and is fixed,
+/// so a layout doesn't add new gate semantics. It only expands into add and mul rows,
+/// which the prover solves and the selector polynomial encodes like any other row.
+/// The output of the layout is the out cell of the last row.
+///
+/// x^3 is laid out as
+///
+/// | lhs    | rhs    | out | s   |
+/// |--------|--------|-----|-----|
+/// | x      | x      | x^2 | Mul |
+/// | row 0  | x      | x^3 | Mul |
+pub trait GateLayout {
+    /// Operation and operands (lhs, rhs) of each row.
+    fn rows(&self) -> Vec<(Op, GateOperand, GateOperand)>;
+
+    /// Selector of each row, 1 on addition rows and 0 on multiplication rows.
+    fn selector_layout(&self) -> Vec<Op> {
+        self.rows().into_iter().map(|(op, _, _)| op).collect()
+    }
+}
+
+impl dyn GateLayout + '_ {
+    /// Compute the output of the layout from its inputs row by row.
+    pub fn evaluate<F: Field>(&self, inputs: &[F]) -> Result<F> {
+        self.validate(inputs.len())?;
+
+        let mut outs: Vec<F> = vec![];
+        for (op, lhs, rhs) in self.rows() {
+            let value = |operand| match operand {
+                GateOperand::Input(i) => inputs[i],
+                GateOperand::Row(j) => outs[j],
+            };
+            let (lhs, rhs) = (value(lhs), value(rhs));
+            outs.push(match op {
+                Op::Add => lhs + rhs,
+                Op::Mul => lhs * rhs,
+            });
+        }
+
+        Ok(outs[outs.len() - 1])
+    }
+
+    /// Check the layout has rows and every operand refers to an input or an earlier row.
+    pub(crate) fn validate(&self, n_inputs: usize) -> Result<()> {
+        let rows = self.rows();
+        if rows.is_empty() {
+            return Err(anyhow!("Gate layout has no rows."));
+        }
+
+        for (row, (_, lhs, rhs)) in rows.iter().enumerate() {
+            for operand in [lhs, rhs] {
+                match *operand {
+                    GateOperand::Input(i) if i >= n_inputs => {
+                        return Err(anyhow!("Row {}: Gate input {} does not exist.", row, i));
+                    }
+                    GateOperand::Row(j) if j >= row => {
+                        return Err(anyhow!("Row {}: Row {} is not computed yet.", row, j));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+
+    struct Cube;
+
+    impl GateLayout for Cube {
+        fn rows(&self) -> Vec<(Op, GateOperand, GateOperand)> {
+            vec![
+                (Op::Mul, GateOperand::Input(0), GateOperand::Input(0)),
+                (Op::Mul, GateOperand::Row(0), GateOperand::Input(0)),
+            ]
+        }
+    }
+
+    struct Invalid;
+
+    impl GateLayout for Invalid {
+        fn rows(&self) -> Vec<(Op, GateOperand, GateOperand)> {
+            vec![(Op::Add, GateOperand::Input(0), GateOperand::Row(0))]
+        }
+    }
+
+    #[test]
+    fn test_evaluate_gate_layout() {
+        let gate: &dyn GateLayout = &Cube;
+        assert_eq!(gate.selector_layout(), vec![Op::Mul, Op::Mul]);
+        assert_eq!(gate.evaluate(&[Fr::from(3)]).unwrap(), Fr::from(27));
+
+        let error = gate.evaluate::<Fr>(&[]).unwrap_err();
+        assert_eq!(format!("{}", error), "Row 0: Gate input 0 does not exist.");
+
+        let gate: &dyn GateLayout = &Invalid;
+        let error = gate.evaluate(&[Fr::from(3)]).unwrap_err();
+        assert_eq!(format!("{}", error), "Row 0: Row 0 is not computed yet.");
+    }
+}
@@ -0,0 +1,153 @@
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+
+use crate::circuit::{Cellref, Circuit, CircuitBuilder, InputConfig};
+
+/*
+Line based circuit IR.
+
+  input pub 2         # number of public inputs
+  input priv 1        # number of private inputs
+  add in0 in2 -> a    # a = in0 + in2
+  mul a in1 -> b      # b = a * in1
+  add b 5 -> out      # numbers are constant cells
+  output out          # optional, must be the output of the last gate
+
+Inputs are referred as `inK`, the K-th input counting public inputs first.
+Text after `#` is a comment.
+*/
+
+impl Circuit {
+    /// Parse a circuit from the line based IR described above.
+    pub fn from_ir(text: &str) -> Result<Circuit> {
+        let lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.split('#').next().unwrap_or("").trim()))
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(n, line)| (n, line.split_whitespace().collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+
+        let (mut n_pub, mut n_priv) = (0, 0);
+        for (n, tokens) in lines.iter() {
+            match tokens[..] {
+                ["input", "pub", count] => n_pub += parse_count(*n, count)?,
+                ["input", "priv", count] => n_priv += parse_count(*n, count)?,
+                ["input", ..] => return Err(anyhow!("Line {}: Invalid input declaration.", n)),
+                _ => {}
+            }
+        }
+
+        let mut builder = CircuitBuilder::new(InputConfig::new(n_pub, n_priv));
+        let mut wires = HashMap::new();
+        let mut output = None;
+        for (n, tokens) in lines.iter() {
+            match tokens[..] {
+                ["input", ..] => {}
+                [op @ ("add" | "mul"), lhs, rhs, "->", out] => {
+                    let lhs = operand(&mut builder, &wires, *n, lhs)?;
+                    let rhs = operand(&mut builder, &wires, *n, rhs)?;
+                    let cell = match op {
+                        "add" => builder.add_addition(lhs, rhs),
+                        _ => builder.add_multiplication(lhs, rhs),
+                    }
+                    .with_context(|| format!("Line {}", n))?;
+                    if wires.insert(out, cell).is_some() {
+                        return Err(anyhow!("Line {}: Wire {} is already defined.", n, out));
+                    }
+                }
+                ["output", name] => {
+                    let cell = wires.get(name).ok_or(anyhow!(
+                        "Line {}: Wire {} is not defined.",
+                        n,
+                        name
+                    ))?;
+                    output = Some((*n, *cell));
+                }
+                _ => return Err(anyhow!("Line {}: Cannot parse `{}`.", n, tokens.join(" "))),
+            }
+        }
+
+        let circuit = builder.build()?;
+        if let Some((n, cell)) = output {
+            if cell != Cellref::Wire(circuit.output_id().0) {
+                return Err(anyhow!(
+                    "Line {}: Output should be the output of the last gate.",
+                    n
+                ));
+            }
+        }
+
+        Ok(circuit)
+    }
+}
+
+fn parse_count(n: usize, count: &str) -> Result<usize> {
+    count
+        .parse()
+        .map_err(|_| anyhow!("Line {}: Invalid input count {}.", n, count))
+}
+
+/// Resolve an operand to an input, a constant or a wire defined before.
+fn operand(
+    builder: &mut CircuitBuilder,
+    wires: &HashMap<&str, Cellref>,
+    n: usize,
+    token: &str,
+) -> Result<Cellref> {
+    if let Some(cell) = wires.get(token) {
+        return Ok(*cell);
+    }
+    if let Ok(value) = token.parse::<u64>() {
+        return Ok(builder.add_constant(value));
+    }
+    if let Some(index) = token
+        .strip_prefix("in")
+        .and_then(|k| k.parse::<usize>().ok())
+    {
+        return Ok(Cellref::Input(index + 1));
+    }
+
+    Err(anyhow!("Line {}: Wire {} is not defined.", n, token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::simple_circuit;
+
+    #[test]
+    fn test_from_ir() {
+        // out = (pub_0 + priv_0) * pub_1 + priv_0
+        let ir = "
+            input pub 2
+            input priv 1
+            add in0 in2 -> a  # pub_0 + priv_0
+            mul a in1 -> b
+            add b in2 -> out
+            output out
+        ";
+        let circ = Circuit::from_ir(ir).unwrap();
+
+        assert_eq!(circ, simple_circuit());
+    }
+
+    #[test]
+    fn test_from_ir_errors() {
+        let error = Circuit::from_ir("input pub 1\nadd in0 x -> y").unwrap_err();
+        assert_eq!(format!("{}", error), "Line 2: Wire x is not defined.");
+
+        let error = Circuit::from_ir("input pub 1\nadd in0 in3 -> y").unwrap_err();
+        assert_eq!(
+            format!("{:#}", error),
+            "Line 2: RHS: Input 4 does not exist."
+        );
+
+        let ir = "input pub 1\nadd in0 in0 -> a\nmul a a -> b\noutput a";
+        let error = Circuit::from_ir(ir).unwrap_err();
+        assert_eq!(
+            format!("{}", error),
+            "Line 4: Output should be the output of the last gate."
+        );
+    }
+}
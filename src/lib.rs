@@ -1,49 +1,82 @@
+mod assignment;
 mod circuit;
 mod common;
+mod extension;
+mod gate;
+mod ir;
+mod pcs;
 mod prover;
+mod r1cs;
 mod setup;
+#[cfg(test)]
+pub(crate) mod test_util;
+mod transcript;
 mod types;
 mod verifier;
 
-pub use circuit::{Circuit, CircuitBuilder};
-pub use prover::Prover;
-pub use setup::setup;
-pub use types::{Proof, PublicParameters};
-pub use verifier::Verifier;
+pub use assignment::{InputAssignment, PublicInputValues};
+pub use circuit::{
+    CellId, Circuit, CircuitBuildError, CircuitBuilder, GateView, InputConfig, InputIndex, Op,
+    OutputBinding, PrivateInputs, PublicInputs, RowIndex,
+};
+pub use common::{batch_evaluate, default_poseidon_config, field_from_be_bytes, field_to_be_bytes};
+pub use extension::Fr2;
+pub use gate::{GateLayout, GateOperand};
+pub use pcs::{
+    CommitmentScheme, Ipa, IpaOpening, IpaParams, Kzg, MarlinKzg, MarlinKzgParams,
+    MultiPointOpening,
+};
+pub use prover::{Prover, ProverBuilder, ProverConfig, ProvingStage, DEFAULT_BLINDING_DEGREE};
+pub use r1cs::{Constraint, LinearCombination, Variable};
+pub use setup::{setup, setup_with_scheme};
+pub use transcript::{Keccak, Poseidon, TranscriptEntry, TranscriptHash};
+pub use types::{
+    ExtensionProof, Proof, ProofBatch, ProofWithPublicOutputs, ProverKey, ProverPolynomials,
+    PublicParameters, VerificationCost, VerificationKey, PROOF_VERSION,
+};
+pub use verifier::{Verifier, VerifierBuilder};
 
 #[cfg(test)]
 mod tests {
-    use crate::{circuit::*, prover::*, setup::*, verifier::*};
-
-    use ark_bls12_381::{Bls12_381, Fq as F};
-    use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
-    use ark_poly::univariate::DensePolynomial;
-    use ark_poly_commit::kzg10::KZG10;
-    use ark_std::test_rng;
+    use crate::{
+        assignment::*,
+        circuit::*,
+        gate::*,
+        pcs::{CommitmentScheme, Ipa, Kzg, MarlinKzg},
+        prover::*,
+        setup::*,
+        test_util::simple_circuit,
+        transcript::*,
+        types::{Proof, UniPoly381, VerificationCost},
+        verifier::*,
+    };
 
-    type Poly = DensePolynomial<Bls12_381>;
-    type Sponge = PoseidonSponge<F>;
-    type PCS = KZG10<Bls12_381, Poly>;
+    use ark_bls12_381::Fr as F;
+    use ark_std::{
+        rand::{rngs::StdRng, SeedableRng},
+        test_rng,
+    };
+    use std::{collections::HashMap, io::Cursor};
 
-    // build circuit to calculate
-    // out = (pub_0 + priv_0) * pub_1 + priv_0
-    fn simple_circ() -> Circuit {
-        let mut builder = CircuitBuilder::new(InputConfig::new(2, 1));
-        let (pb_refs, prv_refs) = builder.get_input_refs();
-        let out_0 = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
-        let out_1 = builder.add_multiplication(out_0, pb_refs[1]).unwrap();
-        let _ = builder.add_addition(out_1, prv_refs[0]).unwrap();
+    /// x^3 as two multiplication rows.
+    struct Cube;
 
-        builder.build().unwrap()
+    impl GateLayout for Cube {
+        fn rows(&self) -> Vec<(Op, GateOperand, GateOperand)> {
+            vec![
+                (Op::Mul, GateOperand::Input(0), GateOperand::Input(0)),
+                (Op::Mul, GateOperand::Row(0), GateOperand::Input(0)),
+            ]
+        }
     }
 
     #[test]
     fn test_prove_and_verify() {
         // Setup public parameters
-        let circ = simple_circ();
+        let circ = simple_circuit();
         let public_inputs = vec![F::from(3), F::from(5)];
         let mut rng = test_rng();
-        let degree = 10;
+        let degree = 48;
 
         // setup polynomials
         let pp = setup(&circ, &public_inputs, &mut rng, degree).unwrap();
@@ -54,9 +87,9 @@ mod tests {
             let mut prover = Prover::<F>::new(
                 circ.clone(),
                 pp.clone(),
-                public_inputs.clone(),
-                private_inputs,
-            );
+                InputAssignment::with_values(&circ, &public_inputs, &private_inputs).unwrap(),
+            )
+            .unwrap();
             let result = prover.calculate_witness();
             assert!(result.is_ok());
             // TODO: is output public?
@@ -73,4 +106,1312 @@ mod tests {
             assert!(result);
         }
     }
+
+    #[test]
+    fn test_verify_with_verification_key() {
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+        let vk = pp.verification_key().clone();
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ, pp, inputs).unwrap();
+        let proof = prover.prove().unwrap();
+
+        let mut verifier = Verifier::<F>::from_vk(vk.clone(), public_inputs);
+        assert!(verifier.verify(proof.clone()));
+
+        // proof should not be accepted with different public inputs
+        let mut verifier = Verifier::<F>::from_vk(vk, vec![F::from(3), F::from(6)]);
+        assert!(!verifier.verify(proof));
+    }
+
+    #[test]
+    fn test_prove_with_blinding_degree() {
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        // each blinder above the default raises the degree by 2
+        let pp = setup(&circ, &public_inputs, &mut test_rng(), 52).unwrap();
+
+        let prove = |k: Option<usize>| {
+            let inputs =
+                InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+            let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+            if let Some(k) = k {
+                prover.set_blinding_degree(k).unwrap();
+            }
+            prover.prove().unwrap()
+        };
+        assert_eq!(circ.degree_bound_with_blinding(5), 52);
+
+        // blinders beyond the SRS are rejected before proving
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let error = prover.set_blinding_degree(6).unwrap_err();
+        assert_eq!(
+            format!("{}", error),
+            "Blinding degree 6 needs degree 54, but the SRS supports up to 52."
+        );
+
+        // unblinded proofs are deterministic
+        let unblinded = prove(Some(0));
+        assert_eq!(unblinded.t_comm, prove(Some(0)).t_comm);
+
+        for k in [None, Some(5)] {
+            let proof = prove(k);
+            assert_ne!(proof.t_comm, unblinded.t_comm);
+            assert_ne!(proof.z_comm, unblinded.z_comm);
+
+            let mut verifier = Verifier::<F>::new(circ.clone(), pp.clone(), public_inputs.clone());
+            assert!(verifier.verify(proof));
+        }
+    }
+
+    #[test]
+    fn test_verification_key_to_solidity_constants() {
+        let circ = simple_circuit();
+        let pp = setup(&circ, [F::from(3), F::from(5)], &mut test_rng(), 48).unwrap();
+        let constants = pp.verification_key().to_solidity_constants();
+
+        // 4 G1 points with 2 coordinates and 2 G2 points with 4 coordinates
+        let lines = constants.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 16);
+        assert!(lines[0].starts_with("bytes constant S_COMM_X = hex\""));
+        assert!(lines[15].starts_with("bytes constant BETA_H_Y_C1 = hex\""));
+        for line in lines {
+            let hex = line.split('"').nth(1).unwrap();
+            assert_eq!(hex.len(), 128);
+            assert!(hex.starts_with(&"0".repeat(32)));
+        }
+    }
+
+    #[test]
+    fn test_verify_rejects_circuit_without_gates() {
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let mut pp = setup(&circ, &public_inputs, &mut test_rng(), 48).unwrap();
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let proof = Prover::<F>::new(circ.clone(), pp.clone(), inputs)
+            .unwrap()
+            .prove()
+            .unwrap();
+
+        let mut empty = circ;
+        empty.gates.clear();
+        empty.selectors.clear();
+        pp.vk.gate_ids.clear();
+        let verifier = Verifier::<F>::new(empty, pp, public_inputs);
+        assert_eq!(
+            format!("{}", verifier.check(&proof).unwrap_err()),
+            "Circuit has no gates."
+        );
+    }
+
+    #[test]
+    fn test_prove_and_verify_with_separate_keys() {
+        let mut rng = test_rng();
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+        let (pk, vk) = pp.into_keys();
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::from_pk(circ, pk, inputs).unwrap();
+        let proof = prover.prove().unwrap();
+
+        let mut verifier = Verifier::<F>::from_vk(vk.clone(), public_inputs);
+        assert!(verifier.verify(proof.clone()));
+
+        let mut verifier = Verifier::<F>::from_vk(vk, vec![F::from(3), F::from(6)]);
+        assert!(!verifier.verify(proof));
+    }
+
+    #[test]
+    fn test_prove_with_metadata() {
+        let mut rng = test_rng();
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs)
+            .unwrap()
+            .with_metadata(b"miniplonk 0.1.0".to_vec());
+        let proof = prover.prove().unwrap();
+        assert_eq!(proof.metadata(), b"miniplonk 0.1.0");
+
+        let mut verifier = Verifier::<F>::new(circ, pp, public_inputs);
+        assert!(verifier.verify(proof.clone()));
+
+        // metadata is bound into the challenges
+        let mut altered = proof.clone();
+        altered.metadata = b"miniplonk 0.2.0".to_vec();
+        assert!(!verifier.verify(altered));
+
+        let mut removed = proof;
+        removed.metadata.clear();
+        assert!(!verifier.verify(removed));
+    }
+
+    #[test]
+    fn test_check_public_opening() {
+        let mut rng = test_rng();
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+        let output_id = circ.output_id();
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs)
+            .unwrap()
+            .with_revealed_cells(vec![output_id]);
+        let proof = prover.prove().unwrap();
+
+        let mut verifier = Verifier::<F>::new(circ, pp, public_inputs);
+        assert!(verifier.check_public_opening(&proof, output_id, F::from(57)));
+        assert!(!verifier.check_public_opening(&proof, output_id, F::from(58)));
+        // cells not revealed can't be checked
+        assert!(!verifier.check_public_opening(&proof, CellId(0), F::from(3)));
+
+        // the same point as the output cell, wrapped around the domain
+        let wrapped = CellId(output_id.0 + proof.domain_size);
+        let mut forged = proof.clone();
+        forged.revealed_cells[0].cell = wrapped.0;
+        assert!(!verifier.check_public_opening(&forged, wrapped, F::from(57)));
+        assert!(verifier.verify(proof));
+    }
+
+    #[test]
+    fn test_prove_with_public_outputs() {
+        let mut rng = test_rng();
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+        // output and (3 + 7) * 5 are computed from the private input
+        let cells = [circ.output_id(), CellId(5)];
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let proof = prover.prove_with_public_outputs(&cells).unwrap();
+        assert_eq!(proof.public_outputs(), [F::from(57), F::from(50)]);
+
+        let mut verifier = Verifier::<F>::new(circ, pp, public_inputs);
+        assert!(verifier.verify_with_public_outputs(proof.clone(), &cells));
+
+        let mut tampered = proof.clone();
+        tampered.public_outputs[0] = F::from(58);
+        assert!(!verifier.verify_with_public_outputs(tampered, &cells));
+        // outputs of other cells are not accepted
+        assert!(!verifier.verify_with_public_outputs(proof.clone(), &[CellId(0), CellId(5)]));
+        assert!(!verifier.verify_with_public_outputs(proof, &cells[..1]));
+    }
+
+    #[test]
+    fn test_transcript_logs_match() {
+        let mut rng = test_rng();
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let (proof, prover_log) = prover.prove_with_transcript_log().unwrap();
+
+        let mut verifier = Verifier::<F>::new(circ.clone(), pp.clone(), public_inputs);
+        let (valid, verifier_log) = verifier.verify_with_transcript_log(proof.clone());
+        assert!(valid);
+        assert_eq!(prover_log, verifier_log);
+
+        // β, γ, α and r
+        let squeezed = prover_log
+            .iter()
+            .filter(|entry| matches!(entry, TranscriptEntry::Squeeze(..)))
+            .count();
+        assert_eq!(squeezed, 4);
+
+        // logs diverge from the public inputs
+        let mut verifier = Verifier::<F>::new(circ, pp, vec![F::from(3), F::from(6)]);
+        let (valid, verifier_log) = verifier.verify_with_transcript_log(proof);
+        assert!(!valid);
+        assert_eq!(prover_log[0], verifier_log[0]);
+        assert_ne!(prover_log[1], verifier_log[1]);
+    }
+
+    #[test]
+    fn test_verifier_accessors() {
+        let mut rng = test_rng();
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+        let vk = pp.verification_key().clone();
+
+        let verifier = Verifier::<F>::new(circ.clone(), pp, public_inputs.clone());
+        assert_eq!(verifier.public_inputs(), &public_inputs[..]);
+        assert_eq!(verifier.circuit(), Some(&circ));
+
+        let verifier = Verifier::<F>::from_vk(vk, public_inputs.clone());
+        assert_eq!(verifier.public_inputs(), &public_inputs[..]);
+        assert_eq!(verifier.circuit(), None);
+    }
+
+    #[test]
+    fn test_prove_knowledge_of_preimage() {
+        // prove knowledge of priv_0 such that priv_0 * priv_0 + priv_0 = pub_0
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+        let (_, prv_refs) = builder.get_input_refs();
+        let sq = builder
+            .add_multiplication(prv_refs[0], prv_refs[0])
+            .unwrap();
+        let out = builder.add_addition(sq, prv_refs[0]).unwrap();
+        builder.assert_equals_public(out, 0).unwrap();
+        let circ = builder.build().unwrap();
+
+        let public_inputs = vec![F::from(12)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        let mut prover = Prover::<F>::new(
+            circ.clone(),
+            pp.clone(),
+            InputAssignment::with_values(&circ, &public_inputs, &[F::from(3)]).unwrap(),
+        )
+        .unwrap();
+        let proof = prover.prove().unwrap();
+
+        let mut verifier = Verifier::<F>::new(circ.clone(), pp.clone(), public_inputs);
+        assert!(verifier.verify(proof.clone()));
+
+        let mut verifier = Verifier::<F>::new(circ.clone(), pp.clone(), vec![F::from(13)]);
+        assert!(!verifier.verify(proof));
+
+        // wrong preimage can not produce a proof
+        let inputs = InputAssignment::with_values(&circ, [F::from(12)], &[F::from(4)]).unwrap();
+        let mut prover = Prover::<F>::new(circ, pp, inputs).unwrap();
+        assert!(prover.prove().is_err());
+    }
+
+    #[test]
+    fn test_prove_all_multiplication_circuit() {
+        // priv_0 * priv_0 * priv_0 = pub_0, the selector polynomial is zero
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+        let (_, prv_refs) = builder.get_input_refs();
+        let sq = builder
+            .add_multiplication(prv_refs[0], prv_refs[0])
+            .unwrap();
+        let out = builder.add_multiplication(sq, prv_refs[0]).unwrap();
+        builder.assert_equals_public(out, 0).unwrap();
+        let circ = builder.build().unwrap();
+        assert_eq!(circ.selector_is_constant(), Some(Op::Mul));
+
+        let public_inputs = vec![F::from(27)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(3)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let proof = prover.prove().unwrap();
+
+        let mut verifier = Verifier::<F>::new(circ.clone(), pp.clone(), public_inputs);
+        assert!(verifier.verify(proof.clone()));
+
+        let mut verifier = Verifier::<F>::new(circ, pp, vec![F::from(28)]);
+        assert!(!verifier.verify(proof));
+    }
+
+    #[test]
+    fn test_prove_gate_layout() {
+        // out = priv_0^3 + pub_0 with a cube layout
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let cube = builder.add_gate_layout(&Cube, &[prv_refs[0]]).unwrap();
+        let _ = builder.add_addition(cube, pb_refs[0]).unwrap();
+        let circ = builder.build().unwrap();
+        assert_eq!(circ.n_rows(), 3);
+
+        let public_inputs = vec![F::from(5)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(3)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let proof = prover.prove_statement(F::from(32)).unwrap();
+
+        let mut verifier = Verifier::<F>::new(circ, pp, public_inputs);
+        assert!(verifier.verify(proof));
+    }
+
+    #[test]
+    fn test_verify_constant_time() {
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let proof = prover.prove_statement(F::from(57)).unwrap();
+
+        // an opening and the output claim fail respectively
+        let mut wrong_opening = proof.clone();
+        wrong_opening.evaluations.t += F::from(1);
+        let mut wrong_output = proof.clone();
+        wrong_output.output_claim.as_mut().unwrap().value = F::from(58);
+
+        let mut verifier = Verifier::<F>::new(circ.clone(), pp.clone(), public_inputs);
+        for (proof, expected) in [
+            (proof.clone(), true),
+            (wrong_opening, false),
+            (wrong_output, false),
+        ] {
+            assert_eq!(verifier.verify(proof.clone()), expected);
+            assert_eq!(verifier.verify_constant_time(proof), expected);
+        }
+
+        // proof of other public inputs
+        let mut verifier = Verifier::<F>::new(circ, pp, vec![F::from(3), F::from(6)]);
+        assert!(!verifier.verify(proof.clone()));
+        assert!(!verifier.verify_constant_time(proof));
+    }
+
+    #[test]
+    fn test_prove_statement() {
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        let mut prover = Prover::<F>::new(
+            circ.clone(),
+            pp.clone(),
+            InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap(),
+        )
+        .unwrap();
+
+        // true output is 57
+        let result = prover.prove_statement(F::from(58));
+        let error = result.unwrap_err();
+        assert_eq!(
+            format!("{}", error),
+            "Claimed output 58 does not match computed output 57."
+        );
+
+        let proof = prover.prove_statement(F::from(57)).unwrap();
+        assert_eq!(proof.claimed_output(), Some(F::from(57)));
+
+        let mut verifier = Verifier::<F>::new(circ, pp, public_inputs);
+        assert!(verifier.verify(proof.clone()));
+
+        // tampered claim should be rejected
+        let mut tampered = proof;
+        tampered.output_claim.as_mut().unwrap().value = F::from(58);
+        assert!(!verifier.verify(tampered));
+    }
+
+    #[test]
+    fn test_prove_and_verify_with_ipa() {
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let mut rng = test_rng();
+        let pp = setup_with_scheme::<Ipa, _, _>(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        let mut prover = Prover::<F, Ipa>::new(
+            circ.clone(),
+            pp.clone(),
+            InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap(),
+        )
+        .unwrap();
+        let proof = prover.prove().unwrap();
+
+        let mut verifier = Verifier::<F, Ipa>::new(circ.clone(), pp.clone(), public_inputs);
+        assert!(verifier.verify(proof.clone()));
+
+        let mut verifier = Verifier::<F, Ipa>::new(circ, pp, vec![F::from(3), F::from(6)]);
+        assert!(!verifier.verify(proof));
+    }
+
+    #[test]
+    fn test_prove_and_verify_with_marlin_kzg() {
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let mut rng = test_rng();
+        let pp = setup_with_scheme::<MarlinKzg, _, _>(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        let mut prover = Prover::<F, MarlinKzg>::new(
+            circ.clone(),
+            pp.clone(),
+            InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap(),
+        )
+        .unwrap();
+        let proof = prover.prove().unwrap();
+
+        let mut verifier = Verifier::<F, MarlinKzg>::new(circ.clone(), pp.clone(), public_inputs);
+        assert!(verifier.verify(proof.clone()));
+
+        // a quotient commitment without its degree bound should be rejected
+        let mut tampered = proof.clone();
+        tampered.t_comm.shifted_comm = None;
+        assert!(!verifier.verify(tampered));
+
+        let mut verifier = Verifier::<F, MarlinKzg>::new(circ, pp, vec![F::from(3), F::from(6)]);
+        assert!(!verifier.verify(proof));
+    }
+
+    #[test]
+    fn test_prove_and_verify_with_keccak_transcript() {
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        let mut prover = Prover::<F, Kzg, Keccak>::new(
+            circ.clone(),
+            pp.clone(),
+            InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap(),
+        )
+        .unwrap();
+        let proof = prover.prove().unwrap();
+
+        let mut verifier =
+            Verifier::<F, Kzg, Keccak>::new(circ.clone(), pp.clone(), public_inputs.clone());
+        assert!(verifier.verify(proof.clone()));
+
+        // challenges derived with a different hash do not match
+        let mut verifier = Verifier::<F, Kzg, Poseidon>::new(circ, pp, public_inputs);
+        assert!(!verifier.verify(proof));
+    }
+
+    #[test]
+    fn test_verify_rejects_domain_size_mismatch() {
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let mut rng = test_rng();
+
+        // larger circuit has 18 cells, which needs a domain of size 32
+        let mut builder = CircuitBuilder::new(InputConfig::new(2, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let mut out = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
+        for _ in 0..4 {
+            out = builder.add_multiplication(out, pb_refs[1]).unwrap();
+        }
+        let large_circ = builder.build().unwrap();
+        let large_pp = setup(&large_circ, &public_inputs, &mut rng, 128).unwrap();
+        let inputs =
+            InputAssignment::with_values(&large_circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(large_circ, large_pp, inputs).unwrap();
+        let proof = prover.prove().unwrap();
+
+        let circ = simple_circuit();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+        let verifier = Verifier::<F>::new(circ, pp, public_inputs);
+        assert_eq!(
+            format!("{}", verifier.check(&proof).unwrap_err()),
+            "Proof domain size 32 does not match circuit domain size 16."
+        );
+    }
+
+    #[test]
+    fn test_prove_division() {
+        // prove knowledge of priv_0 such that pub_0 / priv_0 = pub_1
+        let mut builder = CircuitBuilder::new(InputConfig::new(2, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let out = builder.add_division(pb_refs[0], prv_refs[0]).unwrap();
+        builder.assert_equals_public(out, 1).unwrap();
+        let circ = builder.build().unwrap();
+
+        let public_inputs = vec![F::from(10), F::from(2)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 50).unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(5)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let proof = prover.prove().unwrap();
+
+        let mut verifier = Verifier::<F>::new(circ.clone(), pp.clone(), public_inputs);
+        assert!(verifier.verify(proof.clone()));
+
+        let mut verifier = Verifier::<F>::new(circ, pp, vec![F::from(10), F::from(3)]);
+        assert!(!verifier.verify(proof));
+    }
+
+    #[test]
+    fn test_prove_unused_public_input() {
+        // out = (pub_0 + priv_0) * pub_1, pub_2 is not wired anywhere
+        let mut builder = CircuitBuilder::new(InputConfig::new(3, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let out = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
+        let _ = builder.add_multiplication(out, pb_refs[1]).unwrap();
+        let circ = builder.build().unwrap();
+        assert_eq!(circ.unused_inputs(), vec![2]);
+        assert!(simple_circuit().unused_inputs().is_empty());
+
+        let public_inputs = vec![F::from(3), F::from(5), F::from(11)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 50).unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let proof = prover.prove().unwrap();
+
+        let mut verifier = Verifier::<F>::new(circ.clone(), pp.clone(), public_inputs);
+        assert!(verifier.verify(proof.clone()));
+
+        let mut verifier = Verifier::<F>::new(circ, pp, vec![F::from(3), F::from(5), F::from(12)]);
+        assert!(!verifier.verify(proof));
+    }
+
+    #[test]
+    fn test_prove_square_root() {
+        // prove knowledge of priv_0 such that priv_0 * priv_0 = pub_0
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+        assert!(builder.assert_output_equals(0).is_err());
+        let (_, prv_refs) = builder.get_input_refs();
+        let _ = builder
+            .add_multiplication(prv_refs[0], prv_refs[0])
+            .unwrap();
+        builder.assert_output_equals(0).unwrap();
+        assert!(builder.assert_output_equals(1).is_err());
+        let circ = builder.build().unwrap();
+
+        let public_inputs = vec![F::from(49)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, circ.degree_bound()).unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let proof = prover.prove().unwrap();
+
+        let mut verifier = Verifier::<F>::new(circ.clone(), pp.clone(), public_inputs.clone());
+        assert!(verifier.verify(proof.clone()));
+
+        let mut verifier = Verifier::<F>::new(circ.clone(), pp.clone(), vec![F::from(50)]);
+        assert!(!verifier.verify(proof));
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(6)]).unwrap();
+        let mut prover = Prover::<F>::new(circ, pp, inputs).unwrap();
+        assert!(prover.prove().is_err());
+    }
+
+    #[test]
+    fn test_prove_wide_sum() {
+        // pub_0 = priv_0 + priv_1 + priv_2, padded with a zero constant
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 3));
+        let (_, prv_refs) = builder.get_input_refs();
+        let out = builder.add_wide_sum(&prv_refs).unwrap();
+        builder.assert_equals_public(out, 0).unwrap();
+        let circ = builder.build().unwrap();
+        assert_eq!(circ.n_rows(), 2);
+
+        let public_inputs = vec![F::from(12)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 50).unwrap();
+
+        let private_inputs = [F::from(3), F::from(4), F::from(5)];
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &private_inputs).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let proof = prover.prove().unwrap();
+
+        let mut verifier = Verifier::<F>::new(circ.clone(), pp.clone(), public_inputs);
+        assert!(verifier.verify(proof.clone()));
+
+        let mut verifier = Verifier::<F>::new(circ, pp, vec![F::from(13)]);
+        assert!(!verifier.verify(proof));
+    }
+
+    #[test]
+    fn test_prove_and_verify_with_extension_challenge() {
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let proof = prover.prove_with_extension_challenge().unwrap();
+
+        let mut verifier = Verifier::<F>::new(circ.clone(), pp.clone(), public_inputs);
+        assert!(verifier.verify_extension(proof.clone()));
+
+        let mut verifier = Verifier::<F>::new(circ, pp, vec![F::from(3), F::from(6)]);
+        assert!(!verifier.verify_extension(proof));
+    }
+
+    #[test]
+    fn test_extract_proof_elements() {
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ, pp, inputs).unwrap();
+        let proof = prover.prove().unwrap();
+
+        // 5 commitments and 10 KZG opening proofs
+        assert_eq!(proof.commitments().len(), 5);
+        assert_eq!(proof.group_elements().len(), 15);
+        assert_eq!(proof.group_elements()[..5], proof.commitments());
+        // 10 evaluations, openings have no hiding scalar
+        assert_eq!(proof.field_elements(), proof.evaluations().to_vec());
+
+        // output claim adds its value and opening
+        let proof = prover.prove_statement(F::from(57)).unwrap();
+        assert_eq!(proof.group_elements().len(), 16);
+        assert_eq!(proof.field_elements().len(), 11);
+        assert_eq!(proof.field_elements()[10], F::from(57));
+    }
+
+    #[test]
+    fn test_proof_bytes_roundtrip() {
+        let mut rng = test_rng();
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let bytes = prover.prove().unwrap().to_bytes();
+        assert_eq!(bytes[0], 4);
+
+        let proof = Proof::<Kzg>::from_bytes(&bytes).unwrap();
+        let mut verifier = Verifier::<F>::new(circ, pp, public_inputs);
+        assert!(verifier.verify(proof));
+
+        let mut bytes = bytes;
+        // proofs of version 3 had no batched openings
+        bytes[0] = 3;
+        let error = Proof::<Kzg>::from_bytes(&bytes).unwrap_err();
+        assert_eq!(format!("{}", error), "unsupported proof version 3");
+    }
+
+    #[test]
+    fn test_verify_reader() {
+        let mut rng = test_rng();
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let bytes = prover.prove().unwrap().to_bytes();
+
+        let mut verifier = Verifier::<F>::new(circ, pp, public_inputs);
+        assert!(verifier.verify_reader(Cursor::new(&bytes)).unwrap());
+
+        // a proof cut off in the middle of the stream
+        let cut = Cursor::new(&bytes[..bytes.len() / 2]);
+        assert!(verifier.verify_reader(cut).is_err());
+    }
+
+    #[test]
+    fn test_chain_circuits_through_output() {
+        let mut rng = test_rng();
+
+        // circuit A outputs (pub_0 + priv_0) * pub_1 + priv_0 = 57
+        let circ_a = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let pp_a = setup(&circ_a, &public_inputs, &mut rng, 48).unwrap();
+        let inputs = InputAssignment::with_values(&circ_a, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ_a.clone(), pp_a.clone(), inputs).unwrap();
+        let proof_a = prover.prove_statement(F::from(57)).unwrap();
+        assert_eq!(circ_a.output_binding().cell, circ_a.output_id());
+
+        let mut verifier = Verifier::<F>::new(circ_a, pp_a, public_inputs);
+        assert!(verifier.verify(proof_a.clone()));
+
+        // circuit B proves knowledge of priv_0 such that out_A * priv_0 = pub_0
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+        let (_, prv_refs) = builder.get_input_refs();
+        let out_a = builder.bind_external_output(proof_a.claimed_output().unwrap());
+        let out = builder.add_multiplication(out_a, prv_refs[0]).unwrap();
+        builder.assert_equals_public(out, 0).unwrap();
+        let circ_b = builder.build().unwrap();
+
+        let public_inputs = vec![F::from(114)];
+        let pp_b = setup(&circ_b, &public_inputs, &mut rng, 48).unwrap();
+        let inputs = InputAssignment::with_values(&circ_b, &public_inputs, &[F::from(2)]).unwrap();
+        let mut prover = Prover::<F>::new(circ_b.clone(), pp_b.clone(), inputs).unwrap();
+        let proof_b = prover.prove().unwrap();
+
+        let mut verifier = Verifier::<F>::new(circ_b.clone(), pp_b.clone(), public_inputs);
+        assert!(verifier.verify(proof_b.clone()));
+        let mut verifier = Verifier::<F>::new(circ_b, pp_b, vec![F::from(115)]);
+        assert!(!verifier.verify(proof_b));
+    }
+
+    #[test]
+    fn test_chain_circuits_through_output_near_modulus() {
+        let mut rng = test_rng();
+
+        // (3 + priv_0) * 5 + priv_0 = -1 for priv_0 = -8/3
+        let circ_a = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let pp_a = setup(&circ_a, &public_inputs, &mut rng, 48).unwrap();
+        let private_inputs = [-F::from(8) / F::from(3)];
+        let inputs =
+            InputAssignment::with_values(&circ_a, &public_inputs, &private_inputs).unwrap();
+        let mut prover = Prover::<F>::new(circ_a, pp_a, inputs).unwrap();
+        let proof_a = prover.prove_statement(-F::from(1)).unwrap();
+
+        // circuit B proves out_A * priv_0 = pub_0
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+        let (_, prv_refs) = builder.get_input_refs();
+        let out_a = builder.bind_external_output(proof_a.claimed_output().unwrap());
+        let out = builder.add_multiplication(out_a, prv_refs[0]).unwrap();
+        builder.assert_equals_public(out, 0).unwrap();
+        let circ_b = builder.build().unwrap();
+
+        let public_inputs = vec![-F::from(2)];
+        let pp_b = setup(&circ_b, &public_inputs, &mut rng, 48).unwrap();
+        let inputs = InputAssignment::with_values(&circ_b, &public_inputs, &[F::from(2)]).unwrap();
+        let mut prover = Prover::<F>::new(circ_b.clone(), pp_b.clone(), inputs).unwrap();
+        let proof_b = prover.prove().unwrap();
+
+        let mut verifier = Verifier::<F>::new(circ_b.clone(), pp_b.clone(), public_inputs);
+        assert!(verifier.verify(proof_b.clone()));
+        let mut verifier = Verifier::<F>::new(circ_b, pp_b, vec![F::from(2)]);
+        assert!(!verifier.verify(proof_b));
+    }
+
+    #[test]
+    fn test_prove_circuits_sharing_srs() {
+        let mut rng = test_rng();
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        // priv_0 * priv_0 + priv_0 = pub_0
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+        let (_, prv_refs) = builder.get_input_refs();
+        let sq = builder
+            .add_multiplication(prv_refs[0], prv_refs[0])
+            .unwrap();
+        let out = builder.add_addition(sq, prv_refs[0]).unwrap();
+        builder.assert_equals_public(out, 0).unwrap();
+        let other_circ = builder.build().unwrap();
+        assert!(pp.supports_circuit(&other_circ));
+        let other_pp = pp.for_circuit(&other_circ, [F::from(12)]).unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let proof = prover.prove().unwrap();
+        let mut verifier = Verifier::<F>::new(circ, pp.clone(), public_inputs);
+        assert!(verifier.verify(proof));
+
+        let other_inputs = vec![F::from(12)];
+        let inputs =
+            InputAssignment::with_values(&other_circ, &other_inputs, &[F::from(3)]).unwrap();
+        let mut prover = Prover::<F>::new(other_circ.clone(), other_pp.clone(), inputs).unwrap();
+        let proof = prover.prove().unwrap();
+        let mut verifier = Verifier::<F>::new(other_circ, other_pp, other_inputs);
+        assert!(verifier.verify(proof));
+
+        // circuit needing a larger domain is not supported
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let mut out = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
+        for _ in 0..8 {
+            out = builder.add_multiplication(out, pb_refs[0]).unwrap();
+        }
+        let large_circ = builder.build().unwrap();
+        assert!(!pp.supports_circuit(&large_circ));
+        assert!(pp.for_circuit(&large_circ, [F::from(1)]).is_err());
+    }
+
+    #[test]
+    fn test_prove_many_witnesses() {
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let witnesses = [vec![F::from(1)], vec![F::from(2)], vec![F::from(7)]];
+        let batch = prover.prove_many(&witnesses).unwrap();
+        assert_eq!(batch.proofs().len(), 3);
+
+        let mut verifier = Verifier::<F>::new(circ.clone(), pp.clone(), public_inputs);
+        assert!(verifier.verify_batch(batch.clone()));
+
+        // prover's own witness is kept
+        let proof = prover.prove_statement(F::from(57)).unwrap();
+        assert!(verifier.verify(proof));
+
+        let mut verifier = Verifier::<F>::new(circ, pp, vec![F::from(3), F::from(6)]);
+        assert!(!verifier.verify_batch(batch));
+
+        let error = prover
+            .prove_many(&[vec![F::from(1), F::from(2)]])
+            .unwrap_err();
+        assert_eq!(
+            format!("{}", error),
+            "Witness 0 has 2 private inputs, expected 1."
+        );
+    }
+
+    #[test]
+    fn test_set_inputs() {
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let first = prover.prove_statement(F::from(57)).unwrap();
+
+        // (3 + 1) * 5 + 1, the witness of 7 is not reused
+        prover.set_inputs(&public_inputs, &[F::from(1)]).unwrap();
+        let second = prover.prove_statement(F::from(21)).unwrap();
+        assert_ne!(first.t_comm, second.t_comm);
+
+        let mut verifier = Verifier::<F>::new(circ, pp, public_inputs.clone());
+        assert!(verifier.verify(first));
+        assert!(verifier.verify(second));
+
+        let error = prover.set_inputs(&public_inputs, &[]).unwrap_err();
+        assert_eq!(
+            format!("{}", error),
+            "Expected 2 public and 1 private inputs, got 2 and 0."
+        );
+    }
+
+    /// β, γ, α, r and v of the simple circuit proved with blinders seeded by 1.
+    /// A change of these values breaks compatibility with proofs of earlier versions.
+    const GOLDEN: [&str; 5] = [
+        "11362746854296518816318103908091658829530548255403298147306151968687714772169",
+        "16436870674551148441281035142493090042033018109418475989942243859563967656969",
+        "36685833044679264217280326751920425425653213438978026794432983178371119501128",
+        "49479064069389546647325068846036292330919347462448162127398686705022086842423",
+        "8028664595118186417104200941490257863650357646402256671487913835561248657029",
+    ];
+
+    #[test]
+    fn test_proof_challenges() {
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut verifier = Verifier::<F>::new(circ.clone(), pp.clone(), public_inputs.clone());
+
+        for batch_openings in [false, true] {
+            let config = ProverConfig {
+                batch_openings,
+                ..Default::default()
+            };
+            let mut prover =
+                Prover::<F>::new_with_config(circ.clone(), pp.clone(), inputs.clone(), config)
+                    .unwrap();
+            let proof = prover
+                .prove_with_rng(&mut StdRng::seed_from_u64(1))
+                .unwrap();
+            let challenges = proof.challenges(&public_inputs);
+            let expected = if batch_openings {
+                &GOLDEN[..]
+            } else {
+                &GOLDEN[..4]
+            };
+            assert_eq!(
+                challenges.iter().map(F::to_string).collect::<Vec<_>>(),
+                expected
+            );
+
+            // the verifier squeezes the same challenges
+            let (valid, log) = verifier.verify_with_transcript_log(proof);
+            assert!(valid);
+            let squeezed = log
+                .into_iter()
+                .filter_map(|entry| match entry {
+                    TranscriptEntry::Squeeze(_, challenge) => Some(challenge),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(challenges, squeezed);
+        }
+    }
+
+    #[test]
+    fn test_public_input_values() {
+        let circ = simple_circuit();
+        let config = circ.input_config();
+        let public_inputs =
+            PublicInputValues::try_from((&config, vec![F::from(3), F::from(5)])).unwrap();
+
+        // checked values are accepted wherever public inputs are given
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let proof = Prover::<F>::new(circ.clone(), pp.clone(), inputs)
+            .unwrap()
+            .prove()
+            .unwrap();
+        let built = Prover::<F>::builder()
+            .circuit(circ.clone())
+            .params(pp.clone())
+            .public(public_inputs.clone())
+            .private(vec![F::from(7)])
+            .build()
+            .unwrap()
+            .prove()
+            .unwrap();
+        let mut verifier = Verifier::<F>::builder()
+            .circuit(circ.clone())
+            .params(pp.clone())
+            .public(public_inputs.clone())
+            .build()
+            .unwrap();
+        assert!(verifier.verify(built));
+        let mut verifier = Verifier::<F>::new(circ, pp, public_inputs);
+        assert!(verifier.verify(proof));
+
+        for values in [vec![F::from(3)], vec![F::from(3); 3]] {
+            let n = values.len();
+            let error = PublicInputValues::try_from((&config, values)).unwrap_err();
+            assert_eq!(
+                format!("{}", error),
+                format!("Expected 2 public inputs, got {}.", n)
+            );
+        }
+    }
+
+    #[test]
+    fn test_assign_inputs_by_name() {
+        // simple circuit with the private input named
+        let mut builder = CircuitBuilder::new(InputConfig::new(2, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let out_0 = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
+        let out_1 = builder.add_multiplication(out_0, pb_refs[1]).unwrap();
+        let _ = builder.add_addition(out_1, prv_refs[0]).unwrap();
+        builder.name_input(prv_refs[0], "secret_key").unwrap();
+        builder.name_input(pb_refs[1], "factor").unwrap();
+        let circ = builder.build().unwrap();
+
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        let private = HashMap::from([("secret_key", F::from(7))]);
+        let inputs = InputAssignment::with_labels(&circ, &public_inputs, &private).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let first = prover.prove_statement(F::from(57)).unwrap();
+
+        // (3 + 1) * 5 + 1
+        prover.assign("secret_key", F::from(1)).unwrap();
+        let second = prover.prove_statement(F::from(21)).unwrap();
+
+        let mut verifier = Verifier::<F>::new(circ.clone(), pp, public_inputs.clone());
+        assert!(verifier.verify(first));
+        assert!(verifier.verify(second));
+
+        let error = prover.assign("public_key", F::from(1)).unwrap_err();
+        assert_eq!(format!("{}", error), "Input public_key does not exist.");
+        // public inputs are bound at setup
+        let error = prover.assign("factor", F::from(1)).unwrap_err();
+        assert_eq!(format!("{}", error), "Input factor is public.");
+        let error =
+            InputAssignment::with_labels(&circ, &public_inputs, &HashMap::new()).unwrap_err();
+        assert_eq!(
+            format!("{}", error),
+            "Private input secret_key is not assigned."
+        );
+    }
+
+    #[test]
+    fn test_prove_with_config() {
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut verifier = Verifier::<F>::new(circ.clone(), pp.clone(), public_inputs);
+
+        for batch_openings in [false, true] {
+            for hiding in [false, true] {
+                let config = ProverConfig {
+                    batch_openings,
+                    hiding,
+                    blinding_degree: DEFAULT_BLINDING_DEGREE,
+                };
+                let mut prover =
+                    Prover::<F>::new_with_config(circ.clone(), pp.clone(), inputs.clone(), config)
+                        .unwrap();
+                let proof = prover.prove().unwrap();
+                // 5 commitments and an opening proof per evaluation or per point
+                let n_openings = if batch_openings { 3 } else { 10 };
+                assert_eq!(proof.group_elements().len(), 5 + n_openings);
+                // proofs without hiding are deterministic
+                assert_eq!(prover.prove().unwrap().t_comm == proof.t_comm, !hiding);
+
+                let bytes = proof.to_bytes();
+                assert!(verifier.verify(Proof::<Kzg>::from_bytes(&bytes).unwrap()));
+
+                let mut tampered = proof;
+                tampered.evaluations.z += F::from(1);
+                assert!(!verifier.verify(tampered));
+            }
+        }
+
+        let config = ProverConfig {
+            blinding_degree: 0,
+            ..Default::default()
+        };
+        let error = Prover::<F>::new_with_config(circ, pp, inputs, config).unwrap_err();
+        assert_eq!(
+            format!("{}", error),
+            "Hiding proofs need a positive blinding degree."
+        );
+    }
+
+    #[test]
+    fn test_prove_with_input_visibility() {
+        // pub_1 becomes the first private input
+        let circ = simple_circuit()
+            .with_input_visibility(InputConfig::new(1, 2))
+            .unwrap();
+        assert_eq!(circ.input_config().n_pub(), 1);
+
+        let public_inputs = vec![F::from(3)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+        let inputs =
+            InputAssignment::with_values(&circ, &public_inputs, &[F::from(5), F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let proof = prover.prove_statement(F::from(57)).unwrap();
+
+        let mut verifier = Verifier::<F>::new(circ, pp, public_inputs);
+        assert!(verifier.verify(proof));
+
+        let error = simple_circuit()
+            .with_input_visibility(InputConfig::new(1, 1))
+            .unwrap_err();
+        assert_eq!(format!("{}", error), "Expected 3 inputs in total, got 2.");
+    }
+
+    #[test]
+    fn test_prove_specialized_circuit() {
+        // pub_1 = 5 is folded into the circuit, which still computes the original output
+        let circ = simple_circuit()
+            .specialize(&[(InputIndex(1), F::from(5))])
+            .unwrap();
+
+        let public_inputs = vec![F::from(3)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        let proof = prover.prove_statement(F::from(57)).unwrap();
+
+        let mut verifier = Verifier::<F>::new(circ, pp, public_inputs);
+        assert!(verifier.verify(proof));
+    }
+
+    #[test]
+    fn test_prove_with_rng() {
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+
+        // separate provers draw the same blinders from the same seed
+        let prove = |seed: u64| {
+            let mut prover = Prover::<F>::new(circ.clone(), pp.clone(), inputs.clone()).unwrap();
+            let proof = prover
+                .prove_with_rng(&mut StdRng::seed_from_u64(seed))
+                .unwrap();
+            (proof.to_bytes(), prover.prove().unwrap().to_bytes())
+        };
+        let (first, unseeded) = prove(1);
+        assert_eq!(first, prove(1).0);
+        assert_ne!(first, prove(2).0);
+        assert_ne!(first, unseeded);
+
+        let proof = Proof::<Kzg>::from_bytes(&first).unwrap();
+        let mut verifier = Verifier::<F>::new(circ, pp, public_inputs);
+        assert!(verifier.verify(proof));
+    }
+
+    thread_local! {
+        static KZG_CHECKS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+        static KZG_COMBINED_TERMS: std::cell::Cell<(usize, usize)> =
+            const { std::cell::Cell::new((0, 0)) };
+    }
+
+    /// KZG counting the opening checks and the combined commitments of this thread.
+    #[derive(Clone, Debug)]
+    struct CountingKzg;
+
+    impl CommitmentScheme for CountingKzg {
+        type Params = <Kzg as CommitmentScheme>::Params;
+        type VerifierParams = <Kzg as CommitmentScheme>::VerifierParams;
+        type Commitment = <Kzg as CommitmentScheme>::Commitment;
+        type Opening = <Kzg as CommitmentScheme>::Opening;
+
+        fn setup<R: ark_std::rand::RngCore>(
+            max_degree: usize,
+            rng: &mut R,
+        ) -> anyhow::Result<Self::Params> {
+            Kzg::setup(max_degree, rng)
+        }
+
+        fn max_degree(params: &Self::Params) -> usize {
+            Kzg::max_degree(params)
+        }
+
+        fn verifier_params(params: &Self::Params) -> Self::VerifierParams {
+            Kzg::verifier_params(params)
+        }
+
+        fn commit(params: &Self::Params, poly: &UniPoly381) -> anyhow::Result<Self::Commitment> {
+            Kzg::commit(params, poly)
+        }
+
+        fn open(
+            params: &Self::Params,
+            poly: &UniPoly381,
+            point: F,
+        ) -> anyhow::Result<Self::Opening> {
+            Kzg::open(params, poly, point)
+        }
+
+        fn check(
+            vparams: &Self::VerifierParams,
+            comm: &Self::Commitment,
+            point: F,
+            value: F,
+            opening: &Self::Opening,
+        ) -> anyhow::Result<bool> {
+            KZG_CHECKS.with(|checks| checks.set(checks.get() + 1));
+            Kzg::check(vparams, comm, point, value, opening)
+        }
+
+        fn check_cost(vparams: &Self::VerifierParams) -> VerificationCost {
+            Kzg::check_cost(vparams)
+        }
+
+        fn combine(terms: &[(F, &Self::Commitment)]) -> Self::Commitment {
+            KZG_COMBINED_TERMS.with(|combined| {
+                let (msms, msm_terms) = combined.get();
+                combined.set((msms + 1, msm_terms + terms.len()));
+            });
+            Kzg::combine(terms)
+        }
+
+        fn commitment_to_affine(comm: &Self::Commitment) -> ark_bls12_381::G1Affine {
+            Kzg::commitment_to_affine(comm)
+        }
+
+        fn opening_elements(opening: &Self::Opening) -> (Vec<ark_bls12_381::G1Affine>, Vec<F>) {
+            Kzg::opening_elements(opening)
+        }
+    }
+
+    #[test]
+    fn test_verification_cost() {
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let mut rng = test_rng();
+        let pp =
+            setup_with_scheme::<CountingKzg, _, _>(&circ, &public_inputs, &mut rng, 48).unwrap();
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &[F::from(7)]).unwrap();
+        let mut verifier = Verifier::<F, CountingKzg>::new(circ.clone(), pp.clone(), public_inputs);
+
+        let separate = Prover::<F, CountingKzg>::new(circ.clone(), pp.clone(), inputs.clone())
+            .unwrap()
+            .prove()
+            .unwrap();
+        let batched = Prover::<F, CountingKzg>::new_with_config(
+            circ.clone(),
+            pp.clone(),
+            inputs.clone(),
+            ProverConfig {
+                batch_openings: true,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .prove()
+        .unwrap();
+        // (3 + 7)⋅5 + 7
+        let statement = Prover::<F, CountingKzg>::new(circ, pp, inputs)
+            .unwrap()
+            .prove_statement(F::from(57))
+            .unwrap();
+
+        // Identities take 75 field operations over 16 cells: 9 for Z_H(r), 𝜔r and 𝜔^2r,
+        // 16 for the gates at cells 0, 3 and 6, 39 for the inputs at cells 11 and 10,
+        // and 11 for the permutation. Batching combines 9 evaluations with 18 more, and
+        // the claim on output cell 8 takes 5 to compute its Lagrange basis.
+        // Each KZG check computes 2 pairings and a single term MSM.
+        for (proof, pairings, field_ops) in
+            [(separate, 20, 75), (batched, 6, 93), (statement, 22, 80)]
+        {
+            let cost = verifier.verification_cost(&proof);
+            assert_eq!(cost.pairings, pairings);
+            assert_eq!(cost.field_ops, field_ops);
+
+            KZG_CHECKS.with(|checks| checks.set(0));
+            KZG_COMBINED_TERMS.with(|combined| combined.set((0, 0)));
+            assert!(verifier.verify(proof));
+            let checks = KZG_CHECKS.with(|checks| checks.get());
+            let (msms, msm_terms) = KZG_COMBINED_TERMS.with(|combined| combined.get());
+            assert_eq!(checks * 2, cost.pairings);
+            assert_eq!(checks + msms, cost.msms);
+            assert_eq!(checks + msm_terms, cost.msm_terms);
+        }
+    }
+
+    #[test]
+    fn test_prover_and_verifier_builders() {
+        let circ = simple_circuit();
+        let public_inputs = vec![F::from(3), F::from(5)];
+        let mut rng = test_rng();
+        let pp = setup(&circ, &public_inputs, &mut rng, 48).unwrap();
+
+        let mut prover = Prover::<F>::builder()
+            .circuit(circ.clone())
+            .params(pp.clone())
+            .public(public_inputs.clone())
+            .private(vec![F::from(7)])
+            .config(ProverConfig {
+                batch_openings: true,
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        let proof = prover.prove().unwrap();
+
+        let mut verifier = Verifier::<F>::builder()
+            .params(pp.clone())
+            .public(public_inputs.clone())
+            .circuit(circ.clone())
+            .build()
+            .unwrap();
+        assert!(verifier.verify(proof));
+
+        let error = Prover::<F>::builder()
+            .circuit(circ.clone())
+            .params(pp.clone())
+            .public(public_inputs.clone())
+            .build()
+            .unwrap_err();
+        assert_eq!(format!("{}", error), "Private inputs are not set.");
+
+        // public and private inputs swapped
+        let error = Prover::<F>::builder()
+            .circuit(circ.clone())
+            .params(pp.clone())
+            .public(vec![F::from(7)])
+            .private(public_inputs.clone())
+            .build()
+            .unwrap_err();
+        assert_eq!(format!("{}", error), "Expected 2 public inputs, got 1.");
+
+        let result = Verifier::<F>::builder()
+            .circuit(circ)
+            .public(public_inputs)
+            .build();
+        assert!(result.is_err_and(|e| e.to_string() == "Public parameters are not set."));
+    }
 }
@@ -0,0 +1,629 @@
+use anyhow::{anyhow, Result};
+use ark_bls12_381::{Bls12_381, Fr, G1Affine, G1Projective};
+use ark_crypto_primitives::sponge::{poseidon::PoseidonSponge, CryptographicSponge};
+use ark_ec::CurveGroup;
+use ark_ff::One;
+use ark_poly::Polynomial;
+use ark_poly_commit::{
+    challenge::ChallengeGenerator,
+    ipa_pc::{self, InnerProductArgPC},
+    kzg10::{self, Powers, Randomness, UniversalParams, VerifierKey, KZG10},
+    marlin_pc::{self, MarlinKZG10},
+    LabeledCommitment, LabeledPolynomial, PCCommitterKey, PCRandomness, PolynomialCommitment,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::RngCore;
+use blake2::Blake2s256;
+use std::{borrow::Cow, fmt::Debug};
+
+use crate::{
+    common::{
+        batch_evaluate, compute_quotient_polynomial, compute_vanishing_polynomial,
+        default_poseidon_config, interpolate,
+    },
+    transcript::{Poseidon, Transcript},
+    types::{UniPoly381, VerificationCost},
+};
+
+/// Polynomial commitment scheme used by prover and verifier.
+pub trait CommitmentScheme {
+    /// Parameters used by the prover to commit and open polynomials.
+    /// Shared between threads committing in parallel.
+    type Params: Clone + Sync;
+    /// Parameters used by the verifier to check openings.
+    type VerifierParams: Clone + Debug;
+    type Commitment: Clone
+        + Copy
+        + Send
+        + Debug
+        + Default
+        + PartialEq
+        + CanonicalSerialize
+        + CanonicalDeserialize;
+    type Opening: Clone + Debug + CanonicalSerialize + CanonicalDeserialize;
+
+    /// Setup parameters supporting polynomials up to `max_degree`.
+    fn setup<R: RngCore>(max_degree: usize, rng: &mut R) -> Result<Self::Params>;
+
+    /// Returns the maximum degree of polynomials the parameters can commit to.
+    fn max_degree(params: &Self::Params) -> usize;
+
+    /// Extract the verifier parameters.
+    fn verifier_params(params: &Self::Params) -> Self::VerifierParams;
+
+    /// Commit to a polynomial.
+    fn commit(params: &Self::Params, poly: &UniPoly381) -> Result<Self::Commitment>;
+
+    /// Prove the evaluation of a polynomial at `point`.
+    fn open(params: &Self::Params, poly: &UniPoly381, point: Fr) -> Result<Self::Opening>;
+
+    /// Check that the committed polynomial evaluates to `value` at `point`.
+    fn check(
+        vparams: &Self::VerifierParams,
+        comm: &Self::Commitment,
+        point: Fr,
+        value: Fr,
+        opening: &Self::Opening,
+    ) -> Result<bool>;
+
+    /// Returns the work done by a single `check`.
+    fn check_cost(vparams: &Self::VerifierParams) -> VerificationCost;
+
+    /// Returns the commitment to Σ coeff⋅P of the committed polynomials P.
+    fn combine(terms: &[(Fr, &Self::Commitment)]) -> Self::Commitment;
+
+    /// Prove the evaluations of a polynomial at all of `points` with a single opening.
+    /// Returns the evaluations in the order of the points and the proof.
+    ///
+    /// The quotient W = (P - I) / Z_S is committed, where I interpolates the evaluations
+    /// and Z_S = ∏(X - point). For a challenge x, P - Z_S(x)⋅W is opened at x to I(x).
+    fn open_at_points(
+        params: &Self::Params,
+        poly: &UniPoly381,
+        points: &[Fr],
+    ) -> Result<(Vec<Fr>, MultiPointOpening<Self>)>
+    where
+        Self: Sized,
+    {
+        if points.is_empty() {
+            return Err(anyhow!("Opening needs at least one point."));
+        }
+        let values = batch_evaluate(poly, points);
+        let i_poly = interpolate(points, &values)?;
+        let z_poly = compute_vanishing_polynomial(points.iter().copied());
+        let w_poly = compute_quotient_polynomial(&(poly - &i_poly), &z_poly)?;
+        let w_comm = Self::commit(params, &w_poly)?;
+
+        let comm = Self::commit(params, poly)?;
+        let x = multi_point_challenge(&comm, points, &values, &w_comm);
+        let l_poly = poly - &(&w_poly * z_poly.evaluate(&x));
+        let opening = Self::open(params, &l_poly, x)?;
+
+        Ok((values, MultiPointOpening { w_comm, opening }))
+    }
+
+    /// Check that the committed polynomial evaluates to `values` at `points`.
+    fn check_at_points(
+        vparams: &Self::VerifierParams,
+        comm: &Self::Commitment,
+        points: &[Fr],
+        values: &[Fr],
+        opening: &MultiPointOpening<Self>,
+    ) -> Result<bool>
+    where
+        Self: Sized,
+    {
+        if points.is_empty() || points.len() != values.len() {
+            return Ok(false);
+        }
+        let i_poly = interpolate(points, values)?;
+
+        let x = multi_point_challenge(comm, points, values, &opening.w_comm);
+        let z_x = points.iter().map(|point| x - point).product::<Fr>();
+        let l_comm = Self::combine(&[(Fr::one(), comm), (-z_x, &opening.w_comm)]);
+        Self::check(vparams, &l_comm, x, i_poly.evaluate(&x), &opening.opening)
+    }
+
+    /// Returns the commitment as a curve point.
+    fn commitment_to_affine(comm: &Self::Commitment) -> G1Affine;
+
+    /// Returns the group and field elements making up an opening proof.
+    fn opening_elements(opening: &Self::Opening) -> (Vec<G1Affine>, Vec<Fr>);
+}
+
+/// Opening of a polynomial at several points, produced by `CommitmentScheme::open_at_points`.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct MultiPointOpening<S: CommitmentScheme> {
+    /// Commitment to the quotient W = (P - I) / Z_S.
+    w_comm: S::Commitment,
+    /// Opening of P - Z_S(x)⋅W at the challenge x.
+    opening: S::Opening,
+}
+
+/// Challenge x of a multi-point opening, binding the commitment, points, values and W.
+fn multi_point_challenge(
+    comm: &impl CanonicalSerialize,
+    points: &[Fr],
+    values: &[Fr],
+    w_comm: &impl CanonicalSerialize,
+) -> Fr {
+    let mut transcript = Transcript::<Poseidon>::new();
+    transcript.append_commitment(comm);
+    for scalar in points.iter().chain(values) {
+        transcript.append_scalar(scalar);
+    }
+    transcript.append_commitment(w_comm);
+    transcript.squeeze_challenge()
+}
+
+/// KZG commitment scheme. Requires a trusted setup.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Kzg;
+
+impl Kzg {
+    fn powers(params: &UniversalParams<Bls12_381>) -> Powers<'_, Bls12_381> {
+        Powers {
+            powers_of_g: Cow::Borrowed(&params.powers_of_g),
+            powers_of_gamma_g: Cow::Owned(params.powers_of_gamma_g.values().copied().collect()),
+        }
+    }
+}
+
+impl CommitmentScheme for Kzg {
+    type Params = UniversalParams<Bls12_381>;
+    type VerifierParams = VerifierKey<Bls12_381>;
+    type Commitment = kzg10::Commitment<Bls12_381>;
+    type Opening = kzg10::Proof<Bls12_381>;
+
+    fn setup<R: RngCore>(max_degree: usize, rng: &mut R) -> Result<Self::Params> {
+        Ok(KZG10::<Bls12_381, UniPoly381>::setup(
+            max_degree, false, rng,
+        )?)
+    }
+
+    fn max_degree(params: &Self::Params) -> usize {
+        params.powers_of_g.len() - 1
+    }
+
+    fn verifier_params(params: &Self::Params) -> Self::VerifierParams {
+        VerifierKey {
+            g: params.powers_of_g[0],
+            gamma_g: params.powers_of_gamma_g[&0],
+            h: params.h,
+            beta_h: params.beta_h,
+            prepared_h: params.prepared_h.clone(),
+            prepared_beta_h: params.prepared_beta_h.clone(),
+        }
+    }
+
+    fn commit(params: &Self::Params, poly: &UniPoly381) -> Result<Self::Commitment> {
+        let (comm, _) =
+            KZG10::<Bls12_381, UniPoly381>::commit(&Self::powers(params), poly, None, None)?;
+        Ok(comm)
+    }
+
+    /// The opening is the commitment to (p(x) - p(point)) / (x - point).
+    fn open(params: &Self::Params, poly: &UniPoly381, point: Fr) -> Result<Self::Opening> {
+        let (witness, _) = KZG10::<Bls12_381, UniPoly381>::compute_witness_polynomial(
+            poly,
+            point,
+            &Randomness::empty(),
+        )?;
+        let (comm, _) =
+            KZG10::<Bls12_381, UniPoly381>::commit(&Self::powers(params), &witness, None, None)?;
+        Ok(kzg10::Proof {
+            w: comm.0,
+            random_v: None,
+        })
+    }
+
+    fn check(
+        vparams: &Self::VerifierParams,
+        comm: &Self::Commitment,
+        point: Fr,
+        value: Fr,
+        opening: &Self::Opening,
+    ) -> Result<bool> {
+        Ok(KZG10::<Bls12_381, UniPoly381>::check(
+            vparams, comm, point, value, opening,
+        )?)
+    }
+
+    /// e(C - value⋅G, H) = e(W, βH - point⋅H) without hiding.
+    fn check_cost(_vparams: &Self::VerifierParams) -> VerificationCost {
+        VerificationCost {
+            pairings: 2,
+            msms: 1,
+            msm_terms: 1,
+            field_ops: 0,
+        }
+    }
+
+    fn combine(terms: &[(Fr, &Self::Commitment)]) -> Self::Commitment {
+        let sum = terms
+            .iter()
+            .map(|(coeff, comm)| comm.0 * coeff)
+            .sum::<G1Projective>();
+        kzg10::Commitment(sum.into_affine())
+    }
+
+    fn commitment_to_affine(comm: &Self::Commitment) -> G1Affine {
+        comm.0
+    }
+
+    fn opening_elements(opening: &Self::Opening) -> (Vec<G1Affine>, Vec<Fr>) {
+        (vec![opening.w], opening.random_v.into_iter().collect())
+    }
+}
+
+/// Inner product argument (Bulletproofs style) commitment scheme, wrapping
+/// `InnerProductArgPC` of ark-poly-commit.
+/// Transparent: generators are derived by hashing a public seed, but opening check
+/// takes linear time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Ipa;
+
+type IpaPC = InnerProductArgPC<G1Affine, Blake2s256, UniPoly381, PoseidonSponge<Fr>>;
+
+/// Generators of the IPA commitment.
+/// Number of generators is a power of two.
+pub type IpaParams = ipa_pc::CommitterKey<G1Affine>;
+
+/// IPA opening proof.
+pub type IpaOpening = ipa_pc::Proof<G1Affine>;
+
+/// Challenges combining the opened polynomials of an ark-poly-commit scheme. Only one
+/// polynomial is opened at a time, so prover and verifier start from the same fresh sponge.
+fn challenge_generator() -> ChallengeGenerator<Fr, PoseidonSponge<Fr>> {
+    ChallengeGenerator::new_univariate(&mut PoseidonSponge::new(&default_poseidon_config::<Fr>()))
+}
+
+impl Ipa {
+    fn labeled(poly: &UniPoly381) -> LabeledPolynomial<Fr, UniPoly381> {
+        LabeledPolynomial::new("p".to_string(), poly.clone(), None, None)
+    }
+}
+
+impl CommitmentScheme for Ipa {
+    type Params = IpaParams;
+    type VerifierParams = IpaParams;
+    type Commitment = ipa_pc::Commitment<G1Affine>;
+    type Opening = IpaOpening;
+
+    /// Generators are hashed from the protocol name, so `rng` is not used.
+    fn setup<R: RngCore>(max_degree: usize, rng: &mut R) -> Result<Self::Params> {
+        let pp = IpaPC::setup(max_degree, None, rng)?;
+        let (ck, _) = IpaPC::trim(&pp, max_degree, 0, None)?;
+        Ok(ck)
+    }
+
+    fn max_degree(params: &Self::Params) -> usize {
+        params.supported_degree()
+    }
+
+    fn verifier_params(params: &Self::Params) -> Self::VerifierParams {
+        params.clone()
+    }
+
+    fn commit(params: &Self::Params, poly: &UniPoly381) -> Result<Self::Commitment> {
+        let (comms, _) = IpaPC::commit(params, [&Self::labeled(poly)], None)?;
+        Ok(*comms[0].commitment())
+    }
+
+    fn open(params: &Self::Params, poly: &UniPoly381, point: Fr) -> Result<Self::Opening> {
+        let labeled = Self::labeled(poly);
+        let (comms, rands) = IpaPC::commit(params, [&labeled], None)?;
+        Ok(IpaPC::open(
+            params,
+            [&labeled],
+            &comms,
+            &point,
+            &mut challenge_generator(),
+            &rands,
+            None,
+        )?)
+    }
+
+    fn check(
+        vparams: &Self::VerifierParams,
+        comm: &Self::Commitment,
+        point: Fr,
+        value: Fr,
+        opening: &Self::Opening,
+    ) -> Result<bool> {
+        let rounds = (vparams.supported_degree() + 1).trailing_zeros() as usize;
+        if opening.l_vec.len() != rounds || opening.r_vec.len() != rounds {
+            return Ok(false);
+        }
+
+        let comm = LabeledCommitment::new("p".to_string(), *comm, None);
+        Ok(IpaPC::check(
+            vparams,
+            [&comm],
+            &point,
+            [value],
+            opening,
+            &mut challenge_generator(),
+            None,
+        )?)
+    }
+
+    /// Folding takes two scalar multiplications per round, and the final commitment key
+    /// is a multi-scalar multiplication over all the generators.
+    fn check_cost(vparams: &Self::VerifierParams) -> VerificationCost {
+        let n = vparams.supported_degree() + 1;
+        let rounds = n.trailing_zeros() as usize;
+        VerificationCost {
+            pairings: 0,
+            msms: 2 * rounds + 5,
+            msm_terms: n + 2 * rounds + 5,
+            // x^-1 per round, the check polynomial at the point and its n coefficients
+            field_ops: rounds + rounds * (rounds + 1) / 2 + 2 * rounds + n * rounds / 2 + 3,
+        }
+    }
+
+    fn combine(terms: &[(Fr, &Self::Commitment)]) -> Self::Commitment {
+        let comm = terms
+            .iter()
+            .map(|(coeff, comm)| comm.comm * coeff)
+            .sum::<G1Projective>()
+            .into_affine();
+        ipa_pc::Commitment {
+            comm,
+            shifted_comm: None,
+        }
+    }
+
+    fn commitment_to_affine(comm: &Self::Commitment) -> G1Affine {
+        comm.comm
+    }
+
+    fn opening_elements(opening: &Self::Opening) -> (Vec<G1Affine>, Vec<Fr>) {
+        let points = opening
+            .l_vec
+            .iter()
+            .chain(opening.r_vec.iter())
+            .chain([&opening.final_comm_key])
+            .chain(opening.hiding_comm.iter())
+            .copied()
+            .collect();
+        let scalars = [opening.c].into_iter().chain(opening.rand).collect();
+        (points, scalars)
+    }
+}
+
+/// KZG commitment scheme enforcing a degree bound on every committed polynomial, wrapping
+/// `MarlinKZG10` of ark-poly-commit. Requires a trusted setup.
+///
+/// The SRS has one power more than the bound, and each commitment carries a second
+/// commitment to the polynomial shifted by that power. An opening checks both, so a
+/// polynomial over the bound cannot be opened even by a prover holding the whole SRS.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MarlinKzg;
+
+type MarlinPC = MarlinKZG10<Bls12_381, UniPoly381, PoseidonSponge<Fr>>;
+
+/// Committer and verifier keys of `MarlinKzg`, trimmed to the degree bound.
+#[derive(Clone, Debug)]
+pub struct MarlinKzgParams {
+    ck: marlin_pc::CommitterKey<Bls12_381>,
+    vk: marlin_pc::VerifierKey<Bls12_381>,
+}
+
+impl MarlinKzg {
+    fn labeled(poly: &UniPoly381, degree_bound: usize) -> LabeledPolynomial<Fr, UniPoly381> {
+        LabeledPolynomial::new("p".to_string(), poly.clone(), Some(degree_bound), None)
+    }
+}
+
+impl CommitmentScheme for MarlinKzg {
+    type Params = MarlinKzgParams;
+    type VerifierParams = marlin_pc::VerifierKey<Bls12_381>;
+    type Commitment = marlin_pc::Commitment<Bls12_381>;
+    type Opening = kzg10::Proof<Bls12_381>;
+
+    /// The degree bound is `max_degree`.
+    fn setup<R: RngCore>(max_degree: usize, rng: &mut R) -> Result<Self::Params> {
+        let pp = MarlinPC::setup(max_degree + 1, None, rng)?;
+        let (ck, vk) = MarlinPC::trim(&pp, max_degree, 0, Some(&[max_degree]))?;
+        Ok(MarlinKzgParams { ck, vk })
+    }
+
+    fn max_degree(params: &Self::Params) -> usize {
+        params.ck.supported_degree()
+    }
+
+    fn verifier_params(params: &Self::Params) -> Self::VerifierParams {
+        params.vk.clone()
+    }
+
+    /// Fails if `poly` is over the degree bound.
+    fn commit(params: &Self::Params, poly: &UniPoly381) -> Result<Self::Commitment> {
+        let labeled = Self::labeled(poly, Self::max_degree(params));
+        let (comms, _) = MarlinPC::commit(&params.ck, [&labeled], None)?;
+        Ok(*comms[0].commitment())
+    }
+
+    fn open(params: &Self::Params, poly: &UniPoly381, point: Fr) -> Result<Self::Opening> {
+        let labeled = Self::labeled(poly, Self::max_degree(params));
+        let (comms, rands) = MarlinPC::commit(&params.ck, [&labeled], None)?;
+        Ok(MarlinPC::open(
+            &params.ck,
+            [&labeled],
+            &comms,
+            &point,
+            &mut challenge_generator(),
+            &rands,
+            None,
+        )?)
+    }
+
+    /// Rejects commitments without the shifted commitment, which bounds the degree.
+    fn check(
+        vparams: &Self::VerifierParams,
+        comm: &Self::Commitment,
+        point: Fr,
+        value: Fr,
+        opening: &Self::Opening,
+    ) -> Result<bool> {
+        if comm.shifted_comm.is_none() {
+            return Ok(false);
+        }
+
+        let comm = LabeledCommitment::new("p".to_string(), *comm, Some(vparams.supported_degree));
+        Ok(MarlinPC::check(
+            vparams,
+            [&comm],
+            &point,
+            [value],
+            opening,
+            &mut challenge_generator(),
+            None,
+        )?)
+    }
+
+    /// The commitment, the shifted commitment and the shift power are each scaled by
+    /// a challenge or the value, then checked as a single KZG opening.
+    fn check_cost(_vparams: &Self::VerifierParams) -> VerificationCost {
+        VerificationCost {
+            pairings: 2,
+            msms: 4,
+            msm_terms: 4,
+            field_ops: 1,
+        }
+    }
+
+    fn combine(terms: &[(Fr, &Self::Commitment)]) -> Self::Commitment {
+        let comm = terms
+            .iter()
+            .map(|(coeff, comm)| comm.comm.0 * coeff)
+            .sum::<G1Projective>();
+        let shifted_comm = terms
+            .iter()
+            .map(|(coeff, comm)| comm.shifted_comm.map(|shifted| shifted.0 * coeff))
+            .sum::<Option<G1Projective>>();
+        marlin_pc::Commitment {
+            comm: kzg10::Commitment(comm.into_affine()),
+            shifted_comm: shifted_comm.map(|shifted| kzg10::Commitment(shifted.into_affine())),
+        }
+    }
+
+    fn commitment_to_affine(comm: &Self::Commitment) -> G1Affine {
+        comm.comm.0
+    }
+
+    fn opening_elements(opening: &Self::Opening) -> (Vec<G1Affine>, Vec<Fr>) {
+        (vec![opening.w], opening.random_v.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::UniformRand;
+    use ark_poly::DenseUVPolynomial;
+    use ark_std::{rand::SeedableRng, test_rng};
+
+    #[test]
+    fn test_ipa_open_and_check() {
+        let mut rng = test_rng();
+        let params = Ipa::setup(15, &mut rng).unwrap();
+        let poly = UniPoly381::rand(12, &mut rng);
+        let point = Fr::rand(&mut rng);
+
+        let comm = Ipa::commit(&params, &poly).unwrap();
+        let opening = Ipa::open(&params, &poly, point).unwrap();
+        let value = poly.evaluate(&point);
+
+        assert!(Ipa::check(&params, &comm, point, value, &opening).unwrap());
+        assert!(!Ipa::check(&params, &comm, point, value + Fr::one(), &opening).unwrap());
+    }
+
+    #[test]
+    fn test_ipa_setup_is_transparent() {
+        let params = Ipa::setup(15, &mut test_rng()).unwrap();
+        let other = Ipa::setup(15, &mut ark_std::rand::rngs::StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!(params.comm_key, other.comm_key);
+        assert_eq!(params.h, other.h);
+    }
+
+    #[test]
+    fn test_marlin_kzg_enforces_degree_bound() {
+        let mut rng = test_rng();
+        let params = MarlinKzg::setup(15, &mut rng).unwrap();
+        let vparams = MarlinKzg::verifier_params(&params);
+        let point = Fr::rand(&mut rng);
+
+        let poly = UniPoly381::rand(15, &mut rng);
+        let comm = MarlinKzg::commit(&params, &poly).unwrap();
+        let opening = MarlinKzg::open(&params, &poly, point).unwrap();
+        let value = poly.evaluate(&point);
+        assert!(MarlinKzg::check(&vparams, &comm, point, value, &opening).unwrap());
+        assert!(!MarlinKzg::check(&vparams, &comm, point, value + Fr::one(), &opening).unwrap());
+
+        let over = UniPoly381::rand(16, &mut rng);
+        assert!(MarlinKzg::commit(&params, &over).is_err());
+        assert!(MarlinKzg::open(&params, &over, point).is_err());
+
+        // The SRS has the power needed to commit to `over`, but not the one after it
+        // needed for the shifted commitment.
+        let powers = params
+            .ck
+            .powers
+            .iter()
+            .chain(params.ck.shifted_powers.as_ref().unwrap().last())
+            .copied()
+            .collect::<Vec<_>>();
+        let powers = Powers {
+            powers_of_g: Cow::Owned(powers),
+            powers_of_gamma_g: Cow::Borrowed(&params.ck.powers_of_gamma_g),
+        };
+        let (over_comm, _) =
+            KZG10::<Bls12_381, UniPoly381>::commit(&powers, &over, None, None).unwrap();
+        let (witness, _) = KZG10::<Bls12_381, UniPoly381>::compute_witness_polynomial(
+            &over,
+            point,
+            &Randomness::empty(),
+        )
+        .unwrap();
+        let (w, _) = KZG10::<Bls12_381, UniPoly381>::commit(&powers, &witness, None, None).unwrap();
+        let over_opening = kzg10::Proof {
+            w: w.0,
+            random_v: None,
+        };
+        let over_value = over.evaluate(&point);
+        for shifted_comm in [None, Some(over_comm)] {
+            let forged = marlin_pc::Commitment {
+                comm: over_comm,
+                shifted_comm,
+            };
+            assert!(
+                !MarlinKzg::check(&vparams, &forged, point, over_value, &over_opening).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_open_at_points() {
+        let mut rng = test_rng();
+        let params = Kzg::setup(15, &mut rng).unwrap();
+        let vparams = Kzg::verifier_params(&params);
+        let poly = UniPoly381::rand(12, &mut rng);
+        let r = Fr::rand(&mut rng);
+        let omega = Fr::from(7);
+        let points = [r, omega * r, omega * omega * r];
+
+        let comm = Kzg::commit(&params, &poly).unwrap();
+        let (values, opening) = Kzg::open_at_points(&params, &poly, &points).unwrap();
+        assert_eq!(values, batch_evaluate(&poly, &points));
+        assert!(Kzg::check_at_points(&vparams, &comm, &points, &values, &opening).unwrap());
+
+        let mut wrong = values.clone();
+        wrong[2] += Fr::one();
+        assert!(!Kzg::check_at_points(&vparams, &comm, &points, &wrong, &opening).unwrap());
+        assert!(!Kzg::check_at_points(&vparams, &comm, &points[..2], &values, &opening).unwrap());
+
+        let error = Kzg::open_at_points(&params, &poly, &[r, r]).unwrap_err();
+        assert_eq!(format!("{}", error), "Points should be distinct.");
+    }
+}
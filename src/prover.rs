@@ -1,137 +1,408 @@
 use anyhow::{anyhow, Result};
-use ark_ff::FftField;
+use ark_bls12_381::Fr;
+use ark_ff::{batch_inversion, FftField, Field, One, UniformRand, Zero};
 use ark_poly::{
-    univariate::DensePolynomial, DenseUVPolynomial, EvaluationDomain, GeneralEvaluationDomain,
+    univariate::{DenseOrSparsePolynomial, DensePolynomial},
+    DenseUVPolynomial, EvaluationDomain, Evaluations, GeneralEvaluationDomain, Polynomial,
+};
+use ark_std::rand::{CryptoRng, RngCore};
+use rand_core::OsRng;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::{
+    fmt,
+    marker::PhantomData,
+    ops::Sub,
+    sync::{Arc, Mutex, OnceLock},
 };
-use std::{collections::VecDeque, ops::Sub};
 
 use crate::{
-    circuit::{Circuit, Op},
-    common::{compute_public_input_polynomial, compute_selector_polynomial},
-    types::{Proof, PublicParameters},
+    assignment::InputAssignment,
+    circuit::{CellId, Circuit, Op},
+    common::{
+        compute_quotient_polynomial, compute_selector_polynomial, compute_wire_rotation_polynomial,
+        shift_polynomial, vanishing_over_cells, PublicInputEncoder,
+    },
+    extension::{divide_by_minimal_polynomial, Fr2},
+    pcs::{CommitmentScheme, Kzg},
+    transcript::{public_inputs_digest, Poseidon, Transcript, TranscriptEntry, TranscriptHash},
+    types::{
+        BatchedOpenings, ExtensionOpening, ExtensionProof, Openings, OutputClaim, Proof,
+        ProofBatch, ProofEvaluations, ProofOpenings, ProofWithPublicOutputs, ProverKey,
+        ProverPolynomials, PublicParameters, RevealedCell, UniPoly381,
+    },
 };
 
-pub struct Prover<F: FftField> {
+#[derive(Clone)]
+pub struct Prover<F: FftField, S: CommitmentScheme = Kzg, H: TranscriptHash = Poseidon> {
     circuit: Circuit,
-    public_input: Vec<F>,
-    private_input: Vec<F>,
-    pp: PublicParameters,
+    /// Input values ordered by input number, public inputs first.
+    inputs: Vec<F>,
+    pk: ProverKey<S>,
 
     /// This field stores complete witness data.
     computation_trace: Option<Vec<F>>,
+    /// Encoder of the public input polynomial, shared by every proof of the circuit.
+    public_input_encoder: PublicInputEncoder<F>,
+    /// Metadata embedded in proofs and bound into the transcript.
+    metadata: Vec<u8>,
+    /// Cells whose values are revealed in proofs.
+    revealed_cells: Vec<CellId>,
+    /// Hash deriving the Fiat-Shamir challenges.
+    hash: PhantomData<H>,
+    /// Called when each stage of proving finishes, shared with clones of the prover.
+    progress_callback: Option<ProgressCallback>,
+    /// Number of random coefficients blinding T and Z, drawn from the OS for every proof.
+    blinding_degree: usize,
+    /// Whether polynomials at the same point are opened together.
+    batch_openings: bool,
+    /// Selector polynomial computed by the first proof, fixed by the circuit.
+    selector: OnceLock<DensePolynomial<F>>,
+    /// Whether the witness was corrupted by `corrupt_witness_cell`, in which case
+    /// unsatisfied constraints are proved anyway like a cheating prover would.
+    #[cfg(test)]
+    corrupted: bool,
+}
+
+/// Number of blinders by default, enough to hide T opened at r, 𝜔r and 𝜔^2r.
+pub const DEFAULT_BLINDING_DEGREE: usize = 3;
+
+/// Trade-offs between proof size, verifier work and zero knowledge,
+/// given to `Prover::new_with_config`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProverConfig {
+    /// Open the polynomials at each of r, 𝜔r and 𝜔^2r as a single combination,
+    /// so a proof has 3 opening proofs instead of 10. The verifier combines the
+    /// commitments in turn, and the extension field proofs are never batched.
+    pub batch_openings: bool,
+    /// Blind T and Z, so the proof reveals nothing about the witness.
+    /// Proofs without hiding are deterministic and need a smaller SRS.
+    pub hiding: bool,
+    /// Number of random coefficients blinding T and Z if `hiding` is set.
+    pub blinding_degree: usize,
+}
+
+impl Default for ProverConfig {
+    fn default() -> Self {
+        Self {
+            batch_openings: false,
+            hiding: true,
+            blinding_degree: DEFAULT_BLINDING_DEGREE,
+        }
+    }
+}
+
+/// Builder of a prover naming each argument, created by `Prover::builder`.
+/// Circuit, public parameters, public and private inputs are required.
+pub struct ProverBuilder<F: FftField, S: CommitmentScheme = Kzg, H: TranscriptHash = Poseidon> {
+    circuit: Option<Circuit>,
+    pp: Option<PublicParameters<S>>,
+    public: Option<Vec<F>>,
+    private: Option<Vec<F>>,
+    config: ProverConfig,
+    hash: PhantomData<H>,
+}
+
+impl<F: FftField, S: CommitmentScheme, H: TranscriptHash> ProverBuilder<F, S, H> {
+    pub fn circuit(mut self, circuit: Circuit) -> Self {
+        self.circuit = Some(circuit);
+        self
+    }
+
+    pub fn params(mut self, pp: PublicParameters<S>) -> Self {
+        self.pp = Some(pp);
+        self
+    }
+
+    /// Set the values of the public inputs in order.
+    pub fn public(mut self, public: impl Into<Vec<F>>) -> Self {
+        self.public = Some(public.into());
+        self
+    }
+
+    /// Set the values of the private inputs in order.
+    pub fn private(mut self, private: Vec<F>) -> Self {
+        self.private = Some(private);
+        self
+    }
+
+    /// Prove with `config` instead of the default one.
+    pub fn config(mut self, config: ProverConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Returns error if a required argument is not set or the inputs don't match the circuit.
+    pub fn build(self) -> Result<Prover<F, S, H>> {
+        let circuit = self.circuit.ok_or(anyhow!("Circuit is not set."))?;
+        let pp = self.pp.ok_or(anyhow!("Public parameters are not set."))?;
+        let public = self.public.ok_or(anyhow!("Public inputs are not set."))?;
+        let private = self.private.ok_or(anyhow!("Private inputs are not set."))?;
+
+        let inputs = InputAssignment::with_values(&circuit, &public, &private)?;
+        Prover::new_with_config(circuit, pp, inputs, self.config)
+    }
+}
+
+type ProgressCallback = Arc<Mutex<dyn FnMut(ProvingStage) + Send>>;
+
+/// Major phases of proving, reported to the progress callback in this order.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ProvingStage {
+    /// Every cell of the trace is filled.
+    WitnessDone,
+    /// Trace polynomial is interpolated.
+    TracePolyDone,
+    /// Trace, permutation accumulator and quotient polynomials are committed.
+    Committed,
+    /// Every polynomial is opened at the evaluation points.
+    Opened,
+}
+
+/// Polynomials and their commitments sent before the evaluation point is sampled.
+struct CommittedPolynomials<S: CommitmentScheme> {
+    domain: GeneralEvaluationDomain<Fr>,
+    t_poly: UniPoly381,
+    s_poly: UniPoly381,
+    sigma_poly: UniPoly381,
+    z_poly: UniPoly381,
+    q_gate_poly: UniPoly381,
+    q_input_poly: UniPoly381,
+    q_perm_poly: UniPoly381,
+    t_comm: S::Commitment,
+    z_comm: S::Commitment,
+    q_gate_comm: S::Commitment,
+    q_input_comm: S::Commitment,
+    q_perm_comm: S::Commitment,
+}
+
+/// Polynomials depending only on the circuit and public inputs.
+/// They are shared by proofs of different witnesses.
+struct CircuitPolynomials<F: FftField> {
+    selector: DensePolynomial<F>,
+    wire_rotation: DensePolynomial<F>,
+    public_input: DensePolynomial<F>,
+    gate_vanishing: DensePolynomial<F>,
+    input_vanishing: DensePolynomial<F>,
+}
+
+/// Public parameters are omitted since the SRS is large.
+impl<F: FftField, S: CommitmentScheme, H: TranscriptHash> fmt::Debug for Prover<F, S, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Prover")
+            .field("circuit", &self.circuit)
+            .field("inputs", &self.inputs)
+            .field("computation_trace", &self.computation_trace)
+            .field("metadata", &self.metadata)
+            .finish_non_exhaustive()
+    }
 }
 
-impl<F: FftField> Prover<F> {
+impl<F: FftField, S: CommitmentScheme, H: TranscriptHash> Prover<F, S, H> {
     /// Create new prover instance
+    /// Returns error if the assignment is not for the circuit or has unassigned inputs.
     pub fn new(
         circuit: Circuit,
-        pp: PublicParameters,
-        public_input: Vec<F>,
-        private_input: Vec<F>,
-    ) -> Self {
-        Self {
+        pp: PublicParameters<S>,
+        inputs: InputAssignment<F>,
+    ) -> Result<Self> {
+        Self::from_pk(circuit, pp.pk, inputs)
+    }
+
+    /// Start building a prover with named arguments instead of positional ones.
+    pub fn builder() -> ProverBuilder<F, S, H> {
+        ProverBuilder {
+            circuit: None,
+            pp: None,
+            public: None,
+            private: None,
+            config: ProverConfig::default(),
+            hash: PhantomData,
+        }
+    }
+
+    /// Create prover only from proving key without the verification key.
+    pub fn from_pk(circuit: Circuit, pk: ProverKey<S>, inputs: InputAssignment<F>) -> Result<Self> {
+        if inputs.n_pub() != circuit.input_config.n_pub()
+            || inputs.n_priv() != circuit.input_config.n_priv()
+        {
+            return Err(anyhow!("Input assignment does not match the circuit."));
+        }
+
+        Ok(Self {
+            inputs: inputs.ordered_values()?,
+            public_input_encoder: PublicInputEncoder::new(&circuit)?,
             circuit,
-            pp,
-            public_input,
-            private_input,
+            pk,
             computation_trace: None,
+            metadata: vec![],
+            revealed_cells: vec![],
+            hash: PhantomData,
+            progress_callback: None,
+            blinding_degree: DEFAULT_BLINDING_DEGREE,
+            batch_openings: false,
+            selector: OnceLock::new(),
+            #[cfg(test)]
+            corrupted: false,
+        })
+    }
+
+    /// Create new prover instance proving with `config`.
+    /// Returns error if hiding is requested without blinders,
+    /// or the SRS doesn't support the blinding degree.
+    pub fn new_with_config(
+        circuit: Circuit,
+        pp: PublicParameters<S>,
+        inputs: InputAssignment<F>,
+        config: ProverConfig,
+    ) -> Result<Self> {
+        if config.hiding && config.blinding_degree == 0 {
+            return Err(anyhow!("Hiding proofs need a positive blinding degree."));
         }
+
+        let mut prover = Self::new(circuit, pp, inputs)?;
+        prover.set_blinding_degree(if config.hiding {
+            config.blinding_degree
+        } else {
+            0
+        })?;
+        prover.batch_openings = config.batch_openings;
+        Ok(prover)
     }
 
-    /// Calculate all intermediate witness values in a circuit gate by gate.
-    pub fn calculate_witness(&mut self) -> Result<()> {
-        // assign input wirings to the cells
-        let n_inputs = self.circuit.n_inputs();
-        let n_cells = self.circuit.n_cells();
-        let n_rows = self.circuit.n_rows();
-
-        let mut trace: Vec<Option<F>> = vec![None; n_cells];
-        let mut eval_queue = VecDeque::<usize>::new();
-
-        for i in 0..n_inputs {
-            let value = self
-                .get_input_val(i)
-                .expect("Value within 0..total_input should not panic");
-
-            // assign input cell and its copy constrained cells
-            let id = n_cells - (i + 1);
-            trace[id] = Some(value);
-
-            self.circuit
-                .get_copy_constraints(id)
-                .unwrap()
-                .iter()
-                .for_each(|cell_id| {
-                    trace[*cell_id] = Some(value);
-
-                    let row = cell_id / 3;
-                    if row < n_rows {
-                        // This is actually a gate constraint
-                        let lhs = trace[row * 3];
-                        let rhs = trace[row * 3 + 1];
-                        let out = trace[row * 3 + 2];
-                        if lhs.is_some() && rhs.is_some() && out.is_none() {
-                            eval_queue.push_back(row * 3 + 2);
-                        }
-                    }
-                });
-        }
-
-        // loop queue until it's all calculated
-        while let Some(id) = eval_queue.pop_front() {
-            let lhs = trace[id - 2].unwrap();
-            let rhs = trace[id - 1].unwrap();
-            let op = self.circuit.get_selector(id / 3).unwrap();
-            let value = match op {
-                Op::Add => lhs + rhs,
-                Op::Mul => lhs * rhs,
-            };
-
-            print!(
-                "Id: {}, Assign value {}({}, {}) = {}",
-                id,
-                if op == Op::Add { "ADD" } else { "MUL" },
-                lhs,
-                rhs,
-                value
-            );
-
-            trace[id] = Some(value);
-            if id == self.circuit.output_id() {
-                continue;
-            }
+    /// Embed `metadata` such as the crate version or a nonce in the proofs.
+    /// It is absorbed into the transcript before the first challenge, so it can't be altered.
+    pub fn with_metadata(mut self, metadata: Vec<u8>) -> Self {
+        self.metadata = metadata;
+        self
+    }
 
-            self.circuit
-                .get_copy_constraints(id)
-                .unwrap()
-                .iter()
-                .for_each(|cell_id| {
-                    trace[*cell_id] = Some(value);
-
-                    let row = cell_id / 3;
-                    if row < n_rows {
-                        // This is actually a gate constraint
-                        let lhs = trace[row * 3];
-                        let rhs = trace[row * 3 + 1];
-                        let out = trace[row * 3 + 2];
-                        if lhs.is_some() && rhs.is_some() && out.is_none() {
-                            eval_queue.push_back(row * 3 + 2);
-                        }
-                    }
-                })
+    /// Reveal the values of `cells` in the proofs with openings of T at their points,
+    /// which the verifier spot-checks with `Verifier::check_public_opening`.
+    pub fn with_revealed_cells(mut self, cells: Vec<CellId>) -> Self {
+        self.revealed_cells = cells;
+        self
+    }
+
+    /// Replace the inputs to prove again with the same circuit and SRS.
+    /// The witness of the previous inputs is discarded.
+    /// Public inputs still have to be the ones given at setup.
+    pub fn set_inputs(&mut self, public: impl AsRef<[F]>, private: &[F]) -> Result<()> {
+        self.inputs = self.circuit.concat_inputs(public.as_ref(), private)?;
+        self.reset_witness();
+        Ok(())
+    }
+
+    /// Replace the value of the private input named `name` by `CircuitBuilder::name_input`.
+    /// Public inputs are fixed at setup, so naming one is an error.
+    /// The witness of the previous inputs is discarded.
+    pub fn assign(&mut self, name: &str, value: F) -> Result<()> {
+        let number = self
+            .circuit
+            .input_number(name)
+            .ok_or(anyhow!("Input {} does not exist.", name))?;
+        if number < self.circuit.input_config.n_pub() {
+            return Err(anyhow!("Input {} is public.", name));
         }
+        self.inputs[number] = value;
+        self.reset_witness();
+        Ok(())
+    }
 
-        debug_assert!(trace.iter().all(|o| o.is_some()), "");
+    /// Discard the witness, so the next proof computes it again from the inputs.
+    pub fn reset_witness(&mut self) {
+        self.computation_trace = None;
+    }
 
-        let trace = trace
-            .into_iter()
-            .collect::<Option<Vec<_>>>()
-            .ok_or(anyhow!("Not all the cells are filled"))?;
-        self.computation_trace = Some(trace);
+    /// Call `callback` each time a stage of proving finishes, e.g. to show progress in a CLI.
+    pub fn set_progress_callback(&mut self, callback: impl FnMut(ProvingStage) + Send + 'static) {
+        self.progress_callback = Some(Arc::new(Mutex::new(callback)));
+    }
 
+    /// Blind T and Z with `k` random coefficients each, so up to `k` evaluations
+    /// of them reveal nothing about the witness. 0 disables blinding.
+    /// Each blinder above `DEFAULT_BLINDING_DEGREE` raises the degree needed by 2.
+    /// Returns error if the SRS doesn't support the degree needed with `k` blinders.
+    pub fn set_blinding_degree(&mut self, k: usize) -> Result<()> {
+        let degree = self.circuit.degree_bound_with_blinding(k);
+        let max_degree = S::max_degree(&self.pk.params);
+        if degree > max_degree {
+            return Err(anyhow!(
+                "Blinding degree {} needs degree {}, but the SRS supports up to {}.",
+                k,
+                degree,
+                max_degree
+            ));
+        }
+        self.blinding_degree = k;
         Ok(())
     }
 
+    fn report_progress(&self, stage: ProvingStage) {
+        if let Some(callback) = &self.progress_callback {
+            (callback.lock().expect("Progress callback should not panic"))(stage);
+        }
+    }
+
+    /// Calculate all intermediate witness values in a circuit gate by gate.
+    /// A gate is evaluated once its operands are filled, so gates may be defined in any order.
+    pub fn calculate_witness(&mut self) -> Result<()> {
+        self.computation_trace = Some(self.circuit.solve_witness(&self.inputs)?);
+        #[cfg(test)]
+        {
+            self.corrupted = false;
+        }
+
+        Ok(())
+    }
+
+    /// Overwrite `cell` of the computed witness, so tests can check the verifier rejects
+    /// proofs of a witness violating the constraints.
+    #[cfg(test)]
+    pub(crate) fn corrupt_witness_cell(&mut self, cell: CellId, value: F) {
+        let trace = self
+            .computation_trace
+            .as_mut()
+            .expect("Witness should be computed before corrupting it");
+        trace[cell.0] = value;
+        self.corrupted = true;
+    }
+
+    /// Whether constraints are proved even if the witness doesn't satisfy them.
+    fn is_corrupted(&self) -> bool {
+        #[cfg(test)]
+        return self.corrupted;
+        #[cfg(not(test))]
+        return false;
+    }
+
+    /// Compute the value of `cell`, e.g. one of the outputs revealed by
+    /// `prove_with_public_outputs`, without the whole witness.
+    /// Gates are evaluated only until the cell is filled, and the witness is left as is.
+    pub fn compute_cell(&self, cell: CellId) -> Result<F> {
+        let cell = cell.0;
+        if cell >= self.circuit.n_cells() {
+            return Err(anyhow!("Cell {} does not exist.", cell));
+        }
+        if let Some(trace) = &self.computation_trace {
+            return Ok(trace[cell]);
+        }
+
+        self.circuit.propagate(&self.inputs, Some(cell))?[cell]
+            .ok_or(anyhow!("Cell {} cannot be computed from the inputs.", cell))
+    }
+
+    /// Compute the output at `output_index` of `Circuit::outputs` without the whole witness.
+    /// Only the gates the output depends on are evaluated, and the witness is left as is.
+    pub fn compute_output(&self, output_index: usize) -> Result<F> {
+        let cell = *self
+            .circuit
+            .outputs()
+            .get(output_index)
+            .ok_or(anyhow!("Output {} does not exist.", output_index))?;
+        self.compute_cell(cell)
+    }
+
     // Compute polynomial that represents whole computation trace.
     pub fn compute_trace_polynomial(&self) -> Result<DensePolynomial<F>> {
         // Evaluation domain should better be radix-2 for efficient FFT.
@@ -145,106 +416,714 @@ impl<F: FftField> Prover<F> {
         let domain = GeneralEvaluationDomain::<F>::new(size)
             .ok_or(anyhow!("Domain cannot be constructed from circuit size"))?;
 
-        let trace = self
+        let mut trace = self
             .computation_trace
             .clone()
             .ok_or(anyhow!("Computation should be complete."))?;
+        // padding cells are zero, see `Circuit::padding_rows`
+        trace.resize(size, F::zero());
 
         Ok(DensePolynomial::from_coefficients_vec(domain.ifft(&trace)))
     }
 
-    /// Prove the statement
-    pub fn prove(&mut self) -> Result<Proof> {
-        // suppose we have polynomial commitment scheme available like KZG.
-        // generate computation trace
-        // setup and cmopute inputs polynomial and selector polynomial => prover parameter
-        // commits to those polynomials. => verifier parameter
-        //
+    /// Returns polynomials computed while proving, in coefficient form.
+    /// This is meant for debugging and testing, quotient of the permutation
+    /// constraint is not included since it depends on the verifier challenges.
+    pub fn debug_polynomials(&mut self) -> Result<ProverPolynomials<F>> {
+        if self.computation_trace.is_none() {
+            self.calculate_witness()?;
+        }
+        let domain_size = self.circuit.n_cells().checked_next_power_of_two().unwrap();
+        let domain = GeneralEvaluationDomain::<F>::new(domain_size)
+            .ok_or(anyhow!("Domain cannot be constructed from circuit size"))?;
+
+        let trace = self.compute_trace_polynomial()?;
+        let circuit_polys = self.compute_circuit_polynomials()?;
+        let gate = self.compute_gate_polynomial(&trace, &circuit_polys, domain);
+        let q_gate = compute_quotient_polynomial(&gate, &circuit_polys.gate_vanishing)
+            .map_err(|_| anyhow!("Gate constraints are not satisfied."))?;
+        let input = trace.sub(&circuit_polys.public_input);
+        let q_input = compute_quotient_polynomial(&input, &circuit_polys.input_vanishing)
+            .map_err(|_| anyhow!("Input constraints are not satisfied."))?;
+
+        Ok(ProverPolynomials {
+            trace,
+            selector: circuit_polys.selector,
+            public_input: circuit_polys.public_input,
+            gate,
+            gate_vanishing: circuit_polys.gate_vanishing,
+            q_gate,
+            input,
+            input_vanishing: circuit_polys.input_vanishing,
+            q_input,
+        })
+    }
+
+    /// Compute selector, wire rotation and public input polynomials,
+    /// and vanishing polynomials of gate points and input cells.
+    fn compute_circuit_polynomials(&self) -> Result<CircuitPolynomials<F>> {
+        if public_inputs_digest(self.public_input()) != self.pk.public_inputs_digest {
+            return Err(anyhow!(
+                "Public inputs do not match the ones used at setup."
+            ));
+        }
+
+        let domain_size = self.circuit.n_cells().checked_next_power_of_two().unwrap();
+        let domain = GeneralEvaluationDomain::<F>::new(domain_size)
+            .ok_or(anyhow!("Domain cannot be constructed from circuit size"))?;
+
+        let gate_ids = self
+            .circuit
+            .gates()
+            .map(|gate| gate.lhs)
+            .collect::<Vec<_>>();
+
+        // the selector is interpolated once, later proofs of the circuit reuse it
+        let selector = match self.selector.get() {
+            Some(selector) => selector.clone(),
+            None => {
+                let selector = compute_selector_polynomial(&self.circuit)?;
+                self.selector.get_or_init(|| selector).clone()
+            }
+        };
+
+        Ok(CircuitPolynomials {
+            selector,
+            wire_rotation: compute_wire_rotation_polynomial(&self.circuit)?,
+            public_input: self.public_input_encoder.encode(self.public_input()),
+            gate_vanishing: vanishing_over_cells(domain, &gate_ids),
+            input_vanishing: self.circuit.input_vanishing_polynomial()?,
+        })
+    }
+
+    /// Compute the gate constraint polynomial.
+    /// S(y)⋅[T(y) + T(𝜔y)] + (1 – S(y))⋅T(y)⋅T(𝜔y) − T(𝜔2y) = 0 on 𝜔^id for each gate id
+    fn compute_gate_polynomial(
+        &self,
+        t_poly: &DensePolynomial<F>,
+        circuit_polys: &CircuitPolynomials<F>,
+        domain: GeneralEvaluationDomain<F>,
+    ) -> DensePolynomial<F> {
+        // T(𝜔y) and T(𝜔2y) are computed by shifting the coefficients of T.
+        let omega = domain.group_gen();
+        let t_omega_poly = shift_polynomial(t_poly, omega);
+        let t_omega2_poly = shift_polynomial(t_poly, omega * omega);
+
+        // S is zero when every gate is a multiplication, so only T(y)⋅T(𝜔y) remains.
+        // S of an all-addition circuit is one only on the gate points, which needs both terms.
+        if self.circuit.selector_is_constant() == Some(Op::Mul) {
+            return &(t_poly * &t_omega_poly) - &t_omega2_poly;
+        }
+
+        let s_poly = &circuit_polys.selector;
+        let one = constant(F::one());
+        &(&(s_poly * &(t_poly + &t_omega_poly)) + &(&(&(&one - s_poly) * t_poly) * &t_omega_poly))
+            - &t_omega2_poly
+    }
+
+    fn public_input(&self) -> &[F] {
+        &self.inputs[..self.circuit.input_config.n_pub()]
+    }
+}
+
+impl<S: CommitmentScheme, H: TranscriptHash> Prover<Fr, S, H> {
+    /// Returns `poly / vanishing`, or error if it has a remainder,
+    /// unless the witness is corrupted and the remainder is dropped.
+    fn quotient(&self, poly: &UniPoly381, vanishing: &UniPoly381) -> Result<UniPoly381> {
+        if self.is_corrupted() {
+            let (q, _) = DenseOrSparsePolynomial::from(poly)
+                .divide_with_q_and_r(&DenseOrSparsePolynomial::from(vanishing))
+                .ok_or(anyhow!("Vanishing polynomial should not be zero"))?;
+            return Ok(q);
+        }
+        compute_quotient_polynomial(poly, vanishing)
+    }
+
+    /// Commit to the trace, permutation accumulator and quotient polynomials.
+    /// Leaves `transcript` right before the evaluation point is sampled.
+    fn commit_polynomials(
+        &mut self,
+        circuit_polys: &CircuitPolynomials<Fr>,
+        transcript: &mut Transcript<H>,
+        rng: &mut dyn RngCore,
+    ) -> Result<CommittedPolynomials<S>> {
         // generate witness
         // calculate trace polynomial and commits to it.
-        // calculate wire rotation polynomial.
         if self.computation_trace.is_none() {
             self.calculate_witness()?;
         }
+        self.report_progress(ProvingStage::WitnessDone);
         let t_poly = self.compute_trace_polynomial()?;
+        self.report_progress(ProvingStage::TracePolyDone);
+
+        let domain_size = self.circuit.n_cells().checked_next_power_of_two().unwrap();
+        let domain = GeneralEvaluationDomain::<Fr>::new(domain_size)
+            .ok_or(anyhow!("Domain cannot be constructed from circuit size"))?;
+        let params = &self.pk.params;
+
+        transcript.append_public_inputs(self.public_input());
+        transcript.append_metadata(&self.metadata);
+
+        let t_evals = domain.fft(&t_poly);
+        let t_poly = blind(t_poly, domain, self.blinding_degree, rng);
 
         // prove following things using polynomial checks
         // 1. gates
         // use zero test, prove S(y)⋅[T(y) + T(𝜔y)] + (1 – S(y))⋅T(y)⋅T(𝜔y) − T(𝜔2y) = 0
-        let s_poly = compute_selector_polynomial::<F>(&self.circuit)?;
+        // on gate points 𝜔^id for each gate id
+        let s_poly = circuit_polys.selector.clone();
+        let one = constant(Fr::one());
+        let gate_poly = self.compute_gate_polynomial(&t_poly, circuit_polys, domain);
+        let q_gate_poly = self
+            .quotient(&gate_poly, &circuit_polys.gate_vanishing)
+            .map_err(|_| anyhow!("Gate constraints are not satisfied."))?;
 
         // 2. Prove T encodes the correct inputs
         // prover and verifier both computes the same public input polynomial v(x)
         // Check equality of T(y) - v(y) = 0 on input domain using zero test
-        let v_poly = compute_public_input_polynomial(&self.circuit, &self.public_input)?;
-        let pi_poly = t_poly.sub(&v_poly);
+        let pi_poly = t_poly.sub(&circuit_polys.public_input);
+        let input_vanishing_poly = &circuit_polys.input_vanishing;
+        // quotient has to be committed, so reject it before dividing if the SRS is too small
+        let q_input_degree = pi_poly
+            .degree()
+            .saturating_sub(input_vanishing_poly.degree());
+        if q_input_degree > S::max_degree(params) {
+            return Err(anyhow!(
+                "Input quotient of degree {} exceeds the supported degree {}.",
+                q_input_degree,
+                S::max_degree(params)
+            ));
+        }
+        let q_input_poly = self
+            .quotient(&pi_poly, input_vanishing_poly)
+            .map_err(|_| anyhow!("Input constraints are not satisfied."))?;
+        debug_assert!(q_input_poly.is_zero() || q_input_poly.degree() == q_input_degree);
 
-        // vanishing polynomial
-        let z_poly = todo!();
-        // divide pi_poly with z_poly
-        let q_poly = todo!();
+        // quotients above don't depend on challenges, T is committed after they are checked.
+        // They are committed together, but absorbed into the transcript after Z.
+        let (t_comm, (q_gate_comm, q_input_comm)) = join(
+            || S::commit(params, &t_poly),
+            || {
+                join(
+                    || S::commit(params, &q_gate_poly),
+                    || S::commit(params, &q_input_poly),
+                )
+            },
+        );
+        let (t_comm, q_gate_comm, q_input_comm) = (t_comm?, q_gate_comm?, q_input_comm?);
+        transcript.append_commitment(&t_comm);
 
-        // verifier has commitments to q and pi
+        // 3. wires
+        // prove T(y) = T(W(y)) using permutation argument.
+        // Z(𝜔^0) = 1, Z(𝜔^(i+1)) = Z(𝜔^i)⋅(T(𝜔^i) + β𝜔^i + γ) / (T(𝜔^i) + βW(𝜔^i) + γ)
+        let beta = transcript.squeeze_challenge();
+        let gamma = transcript.squeeze_challenge();
 
-        // Opening proof of q, pi on random r sampled using fiat-shamir
+        let sigma_poly = circuit_polys.wire_rotation.clone();
+        let sigma_evals = domain.fft(&sigma_poly);
+        // the denominators are inverted at once, then Z is the running product of the ratios
+        let mut dens: Vec<Fr> = (0..domain.size() - 1)
+            .map(|i| t_evals[i] + beta * sigma_evals[i] + gamma)
+            .collect();
+        if dens.iter().any(Zero::is_zero) {
+            return Err(anyhow!("Permutation accumulator cannot be computed."));
+        }
+        batch_inversion(&mut dens);
+        let ratios: Vec<Fr> = domain
+            .elements()
+            .zip(dens)
+            .enumerate()
+            .map(|(i, (x, den))| (t_evals[i] + beta * x + gamma) * den)
+            .collect();
+        #[cfg(feature = "parallel")]
+        let segments = rayon::current_num_threads();
+        #[cfg(not(feature = "parallel"))]
+        let segments = 1;
+        let z_evals = prefix_products(&ratios, segments);
+        let z_poly = Evaluations::from_vec_and_domain(z_evals, domain).interpolate();
+        let z_poly = blind(z_poly, domain, self.blinding_degree, rng);
+        let z_omega_poly = shift_polynomial(&z_poly, domain.group_gen());
 
-        // get input domain
-        let n_cells = self.circuit.n_cells();
-        let domain_size = self.circuit.n_cells().checked_next_power_of_two().unwrap();
-        let evals = self.public_input.to_vec();
-        let mut pad = vec![F::zero(); n_cells - evals.len()];
-        pad.append(&mut evals.iter().rev().copied().collect::<Vec<_>>());
+        let z_comm = S::commit(params, &z_poly)?;
+        transcript.append_commitment(&z_comm);
+        let alpha = transcript.squeeze_challenge();
 
-        let domain = GeneralEvaluationDomain::<F>::new(domain_size).unwrap();
+        // Z(𝜔y)⋅(T(y) + βW(y) + γ) - Z(y)⋅(T(y) + βy + γ) + α⋅L0(y)⋅(Z(y) - 1) = 0 on whole domain
+        let id_poly = DensePolynomial::from_coefficients_vec(vec![Fr::zero(), beta]);
+        let mut l0_evals = vec![Fr::zero(); domain.size()];
+        l0_evals[0] = Fr::one();
+        let l0_poly = Evaluations::from_vec_and_domain(l0_evals, domain).interpolate();
+        let perm_poly = &(&(&z_omega_poly
+            * &(&(&t_poly + &(&sigma_poly * beta)) + &constant(gamma)))
+            - &(&z_poly * &(&(&t_poly + &id_poly) + &constant(gamma))))
+            + &(&(&l0_poly * &(&z_poly - &one)) * alpha);
+        let (q_perm_poly, r) = perm_poly.divide_by_vanishing_poly(domain).unwrap();
+        if !r.is_zero() && !self.is_corrupted() {
+            return Err(anyhow!("Copy constraints are not satisfied."));
+        }
 
-        // 3. wires
-        // 4. output
+        // verifier has commitments to quotients
+        let q_perm_comm = S::commit(params, &q_perm_poly)?;
+        transcript.append_commitment(&q_gate_comm);
+        transcript.append_commitment(&q_input_comm);
+        transcript.append_commitment(&q_perm_comm);
+        self.report_progress(ProvingStage::Committed);
+
+        Ok(CommittedPolynomials {
+            domain,
+            t_poly,
+            s_poly,
+            sigma_poly,
+            z_poly,
+            q_gate_poly,
+            q_input_poly,
+            q_perm_poly,
+            t_comm,
+            z_comm,
+            q_gate_comm,
+            q_input_comm,
+            q_perm_comm,
+        })
+    }
+
+    /// Prove the statement
+    pub fn prove(&mut self) -> Result<Proof<S>> {
+        self.prove_with_rng(&mut OsRng)
+    }
+
+    /// Prove the statement with blinders drawn from `rng`, so a seeded rng gives
+    /// byte-identical proofs, e.g. in tests.
+    pub fn prove_with_rng(&mut self, rng: &mut (impl RngCore + CryptoRng)) -> Result<Proof<S>> {
+        let circuit_polys = self.compute_circuit_polynomials()?;
+        self.prove_with_circuit_polynomials(&circuit_polys, &mut Transcript::new(), None, rng)
+    }
 
-        todo!()
+    /// Prove the statement, recording every value absorbed into and squeezed out of
+    /// the transcript. The log can be compared with `Verifier::verify_with_transcript_log`
+    /// to find where the two parties diverge.
+    pub fn prove_with_transcript_log(&mut self) -> Result<(Proof<S>, Vec<TranscriptEntry>)> {
+        let circuit_polys = self.compute_circuit_polynomials()?;
+        let mut transcript = Transcript::with_log();
+        let proof =
+            self.prove_with_circuit_polynomials(&circuit_polys, &mut transcript, None, &mut OsRng)?;
+        Ok((proof, transcript.log().to_vec()))
     }
 
-    fn get_input_val(&self, id: usize) -> Option<F> {
-        if id < self.public_input.len() {
-            self.public_input.get(id).copied()
+    /// Prove with `circuit_polys` shared by proofs of the circuit.
+    /// `claimed_output` is embedded with the opening of T at the output cell.
+    fn prove_with_circuit_polynomials(
+        &mut self,
+        circuit_polys: &CircuitPolynomials<Fr>,
+        transcript: &mut Transcript<H>,
+        claimed_output: Option<Fr>,
+        rng: &mut dyn RngCore,
+    ) -> Result<Proof<S>> {
+        let committed = self.commit_polynomials(circuit_polys, transcript, rng)?;
+        let CommittedPolynomials {
+            domain,
+            t_poly,
+            s_poly,
+            sigma_poly,
+            z_poly,
+            q_gate_poly,
+            q_input_poly,
+            q_perm_poly,
+            t_comm,
+            z_comm,
+            q_gate_comm,
+            q_input_comm,
+            q_perm_comm,
+        } = committed;
+        let domain_size = domain.size();
+        let omega = domain.group_gen();
+        let params = &self.pk.params;
+
+        // Opening proofs on random r sampled using fiat-shamir
+        let r = transcript.squeeze_challenge();
+        let open = |poly: &UniPoly381, point: Fr| -> Result<(Fr, S::Opening)> {
+            let proof = S::open(params, poly, point)?;
+            Ok((poly.evaluate(&point), proof))
+        };
+
+        let evaluations = ProofEvaluations {
+            t: t_poly.evaluate(&r),
+            t_omega: t_poly.evaluate(&(omega * r)),
+            t_omega2: t_poly.evaluate(&(omega * omega * r)),
+            s: s_poly.evaluate(&r),
+            sigma: sigma_poly.evaluate(&r),
+            z: z_poly.evaluate(&r),
+            z_omega: z_poly.evaluate(&(omega * r)),
+            q_gate: q_gate_poly.evaluate(&r),
+            q_input: q_input_poly.evaluate(&r),
+            q_perm: q_perm_poly.evaluate(&r),
+        };
+
+        let openings = if self.batch_openings {
+            // polynomials at the same point are opened as one combination with powers of v
+            for value in evaluations.values() {
+                transcript.append_scalar(&value);
+            }
+            let v = transcript.squeeze_challenge();
+            let at_r = [
+                &t_poly,
+                &s_poly,
+                &sigma_poly,
+                &z_poly,
+                &q_gate_poly,
+                &q_input_poly,
+                &q_perm_poly,
+            ];
+            Openings::Batched(BatchedOpenings {
+                r: S::open(params, &combine(&at_r, v), r)?,
+                omega_r: S::open(params, &combine(&[&t_poly, &z_poly], v), omega * r)?,
+                omega2_r: S::open(params, &t_poly, omega * omega * r)?,
+            })
         } else {
-            self.private_input
-                .get(id - self.public_input.len())
-                .copied()
+            Openings::Separate(ProofOpenings {
+                t: S::open(params, &t_poly, r)?,
+                t_omega: S::open(params, &t_poly, omega * r)?,
+                t_omega2: S::open(params, &t_poly, omega * omega * r)?,
+                s: S::open(params, &s_poly, r)?,
+                sigma: S::open(params, &sigma_poly, r)?,
+                z: S::open(params, &z_poly, r)?,
+                z_omega: S::open(params, &z_poly, omega * r)?,
+                q_gate: S::open(params, &q_gate_poly, r)?,
+                q_input: S::open(params, &q_input_poly, r)?,
+                q_perm: S::open(params, &q_perm_poly, r)?,
+            })
+        };
+
+        // 4. output
+        // output is not revealed to the verifier unless claimed by `prove_statement`.
+
+        let revealed_cells = self
+            .revealed_cells
+            .iter()
+            .map(|CellId(cell)| {
+                if *cell >= self.circuit.n_cells() {
+                    return Err(anyhow!("Cell {} does not exist.", cell));
+                }
+                let (value, opening) = open(&t_poly, domain.element(*cell))?;
+                Ok(RevealedCell {
+                    cell: *cell,
+                    value,
+                    opening,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let output_claim = claimed_output
+            .map(|value| {
+                let point = domain.element(self.circuit.output_id().0);
+                let opening = S::open(params, &t_poly, point)?;
+                Ok::<_, anyhow::Error>(OutputClaim { value, opening })
+            })
+            .transpose()?;
+        self.report_progress(ProvingStage::Opened);
+
+        Ok(Proof {
+            domain_size,
+            t_comm,
+            z_comm,
+            q_gate_comm,
+            q_input_comm,
+            q_perm_comm,
+            evaluations,
+            openings,
+            output_claim,
+            metadata: self.metadata.clone(),
+            revealed_cells,
+        })
+    }
+
+    /// Prove the circuit for each of `witnesses`, which are the private input values.
+    /// Public inputs are the same for all the proofs, so the polynomials of the circuit
+    /// are computed only once and shared. Each proof is still complete on its own,
+    /// so the batch is as large as the proofs together.
+    /// The prover keeps its own private inputs and witness after this call.
+    pub fn prove_many(&mut self, witnesses: &[Vec<Fr>]) -> Result<ProofBatch<S>> {
+        let n_pub = self.circuit.input_config.n_pub();
+        let n_priv = self.circuit.input_config.n_priv();
+        if let Some((i, witness)) = witnesses
+            .iter()
+            .enumerate()
+            .find(|(_, witness)| witness.len() != n_priv)
+        {
+            return Err(anyhow!(
+                "Witness {} has {} private inputs, expected {}.",
+                i,
+                witness.len(),
+                n_priv
+            ));
         }
+
+        let circuit_polys = self.compute_circuit_polynomials()?;
+        let inputs = self.inputs.clone();
+        let computation_trace = self.computation_trace.take();
+
+        let proofs = witnesses
+            .iter()
+            .map(|witness| {
+                self.inputs.truncate(n_pub);
+                self.inputs.extend(witness);
+                self.computation_trace = None;
+                self.prove_with_circuit_polynomials(
+                    &circuit_polys,
+                    &mut Transcript::new(),
+                    None,
+                    &mut OsRng,
+                )
+            })
+            .collect::<Result<Vec<_>>>();
+
+        self.inputs = inputs;
+        self.computation_trace = computation_trace;
+        Ok(ProofBatch { proofs: proofs? })
     }
+
+    /// Prove the statement with the witness computed by `calculate_witness` beforehand.
+    /// Unlike `prove`, returns error instead of computing the witness if it's missing.
+    pub fn prove_with_precomputed_witness(&mut self) -> Result<Proof<S>> {
+        if self.computation_trace.is_none() {
+            return Err(anyhow!("Witness has not been computed."));
+        }
+        self.prove()
+    }
+
+    /// Prove the statement that the circuit outputs `claimed_output`.
+    /// Returns error before proving if the computed output differs from the claim.
+    /// The claim is embedded in the proof together with the opening of T at the output cell.
+    pub fn prove_statement(&mut self, claimed_output: Fr) -> Result<Proof<S>> {
+        if self.computation_trace.is_none() {
+            self.calculate_witness()?;
+        }
+
+        let output_id = self.circuit.output_id().0;
+        let output = self
+            .computation_trace
+            .as_ref()
+            .and_then(|trace| trace.get(output_id).copied())
+            .ok_or(anyhow!("Computation should be complete."))?;
+        if output != claimed_output {
+            return Err(anyhow!(
+                "Claimed output {} does not match computed output {}.",
+                claimed_output,
+                output
+            ));
+        }
+
+        // T is blinded, so it is opened while proving
+        let circuit_polys = self.compute_circuit_polynomials()?;
+        self.prove_with_circuit_polynomials(
+            &circuit_polys,
+            &mut Transcript::new(),
+            Some(claimed_output),
+            &mut OsRng,
+        )
+    }
+
+    /// Prove the statement and reveal the values of `cells` computed by the witness as
+    /// public outputs, e.g. a hash of private data. Unlike public inputs, they are not
+    /// given at setup, and the verifier checks them with `Verifier::verify_with_public_outputs`.
+    pub fn prove_with_public_outputs(
+        &mut self,
+        cells: &[CellId],
+    ) -> Result<ProofWithPublicOutputs<S>> {
+        let revealed_cells = self.revealed_cells.clone();
+        self.revealed_cells
+            .extend(cells.iter().filter(|cell| !revealed_cells.contains(cell)));
+        let proof = self.prove();
+        self.revealed_cells = revealed_cells;
+        let proof = proof?;
+
+        let public_outputs = cells
+            .iter()
+            .map(|cell| {
+                proof
+                    .revealed_cells
+                    .iter()
+                    .find(|revealed| revealed.cell == cell.0)
+                    .map(|revealed| revealed.value)
+                    .expect("Output cells should be revealed")
+            })
+            .collect();
+
+        Ok(ProofWithPublicOutputs {
+            proof,
+            public_outputs,
+        })
+    }
+
+    /// Prove the statement with the evaluation point r sampled from the extension `Fr2`.
+    /// Each polynomial P is evaluated at r, 𝜔r or 𝜔^2r by dividing it by the minimal
+    /// polynomial of the point. The quotient is committed, and both P and the quotient
+    /// are opened at a base field point z sampled afterwards.
+    pub fn prove_with_extension_challenge(&mut self) -> Result<ExtensionProof<S>> {
+        let circuit_polys = self.compute_circuit_polynomials()?;
+        let mut transcript = Transcript::<H>::new();
+        let committed = self.commit_polynomials(&circuit_polys, &mut transcript, &mut OsRng)?;
+        let params = &self.pk.params;
+        let omega = Fr2::from_base_prime_field(committed.domain.group_gen());
+
+        let r = transcript.squeeze_extension_challenge();
+        let polys = [
+            (&committed.t_poly, r),
+            (&committed.t_poly, omega * r),
+            (&committed.t_poly, omega * omega * r),
+            (&committed.s_poly, r),
+            (&committed.sigma_poly, r),
+            (&committed.z_poly, r),
+            (&committed.z_poly, omega * r),
+            (&committed.q_gate_poly, r),
+            (&committed.q_input_poly, r),
+            (&committed.q_perm_poly, r),
+        ];
+
+        let mut quotients = vec![];
+        let mut values = vec![];
+        for (poly, point) in polys {
+            let (q, value) = divide_by_minimal_polynomial(poly, point)?;
+            let q_comm = S::commit(params, &q)?;
+            quotients.push((q, q_comm));
+            values.push(value);
+        }
+        values
+            .iter()
+            .for_each(|value| transcript.append_extension_scalar(value));
+        quotients
+            .iter()
+            .for_each(|(_, q_comm)| transcript.append_commitment(q_comm));
+
+        let z = transcript.squeeze_challenge();
+        let openings = polys
+            .iter()
+            .zip(quotients)
+            .map(|((poly, _), (q, q_comm))| {
+                Ok(ExtensionOpening {
+                    q_comm,
+                    p_value: poly.evaluate(&z),
+                    q_value: q.evaluate(&z),
+                    p_opening: S::open(params, poly, z)?,
+                    q_opening: S::open(params, &q, z)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(ExtensionProof {
+            domain_size: committed.domain.size(),
+            t_comm: committed.t_comm,
+            z_comm: committed.z_comm,
+            q_gate_comm: committed.q_gate_comm,
+            q_input_comm: committed.q_input_comm,
+            q_perm_comm: committed.q_perm_comm,
+            evaluations: ProofEvaluations {
+                t: values[0],
+                t_omega: values[1],
+                t_omega2: values[2],
+                s: values[3],
+                sigma: values[4],
+                z: values[5],
+                z_omega: values[6],
+                q_gate: values[7],
+                q_input: values[8],
+                q_perm: values[9],
+            },
+            openings,
+            metadata: self.metadata.clone(),
+        })
+    }
+}
+
+/// Returns Σ v^i⋅P_i of `polys`.
+fn combine(polys: &[&UniPoly381], v: Fr) -> UniPoly381 {
+    let mut combined = UniPoly381::zero();
+    for poly in polys.iter().rev() {
+        combined = &(&combined * v) + *poly;
+    }
+    combined
+}
+
+/// Add a multiple of the vanishing polynomial of `domain` with `k` random coefficients,
+/// which keeps the values of `poly` on the domain.
+fn blind(
+    poly: UniPoly381,
+    domain: GeneralEvaluationDomain<Fr>,
+    k: usize,
+    rng: &mut dyn RngCore,
+) -> UniPoly381 {
+    let blinder = (0..k).map(|_| Fr::rand(rng)).collect();
+    &poly + &DensePolynomial::from_coefficients_vec(blinder).mul_by_vanishing_poly(domain)
+}
+
+/// Running products 1, f_0, f_0⋅f_1, .. of `factors`, one element longer than it.
+///
+/// `factors` is split into `segments` contiguous segments, each scanned on its own thread
+/// with the `parallel` feature. Their running products are then scaled by the product of
+/// the segments before them. Multiplication is exact, so the result doesn't depend on `segments`.
+fn prefix_products<F: Field>(factors: &[F], segments: usize) -> Vec<F> {
+    let segment_len = factors.len().div_ceil(segments.max(1)).max(1);
+    let mut products = Vec::with_capacity(factors.len() + 1);
+    products.push(F::one());
+    products.extend_from_slice(factors);
+
+    #[cfg(feature = "parallel")]
+    let chunks = products[1..].par_chunks_mut(segment_len);
+    #[cfg(not(feature = "parallel"))]
+    let chunks = products[1..].chunks_mut(segment_len);
+    chunks.for_each(|chunk| {
+        for i in 1..chunk.len() {
+            chunk[i] = chunk[i - 1] * chunk[i];
+        }
+    });
+
+    // product of the segments before each segment
+    let mut carries = Vec::with_capacity(segments);
+    let mut carry = F::one();
+    for chunk in products[1..].chunks(segment_len) {
+        carries.push(carry);
+        carry *= chunk[chunk.len() - 1];
+    }
+
+    #[cfg(feature = "parallel")]
+    let chunks = products[1..].par_chunks_mut(segment_len);
+    #[cfg(not(feature = "parallel"))]
+    let chunks = products[1..].chunks_mut(segment_len);
+    chunks.zip(carries).skip(1).for_each(|(chunk, carry)| {
+        for product in chunk {
+            *product *= carry;
+        }
+    });
+
+    products
+}
+
+/// Run `a` and `b` on two threads with the `parallel` feature, one after another without.
+fn join<A: Send, B: Send>(a: impl FnOnce() -> A + Send, b: impl FnOnce() -> B + Send) -> (A, B) {
+    #[cfg(feature = "parallel")]
+    return rayon::join(a, b);
+    #[cfg(not(feature = "parallel"))]
+    return (a(), b());
+}
+
+/// Constant polynomial.
+fn constant<F: FftField>(c: F) -> DensePolynomial<F> {
+    DensePolynomial::from_coefficients_vec(vec![c])
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        circuit::{Circuit, CircuitBuilder, InputConfig},
-        types::UniPoly381,
+        circuit::{Cellref, Circuit, CircuitBuilder, InputConfig},
+        setup::setup,
+        test_util::{bls_params, simple_circuit, simple_inputs},
+        verifier::Verifier,
+    };
+    use ark_bls12_381::Fq;
+    use ark_ff::{BigInt, BigInteger, PrimeField};
+    use ark_std::{
+        rand::{rngs::StdRng, SeedableRng},
+        test_rng,
     };
-    use ark_bls12_381::{Bls12_381, Fq};
-    use ark_poly::Polynomial;
-    use ark_poly_commit::kzg10::KZG10;
-    use ark_std::test_rng;
-
-    // build circuit to calculate
-    // out = (pub_0 + priv_0) * pub_1 + priv_0
-    fn simple_circ() -> Circuit {
-        let mut builder = CircuitBuilder::new(InputConfig::new(2, 1));
-        let (pb_refs, prv_refs) = builder.get_input_refs();
-        let out_0 = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
-        let out_1 = builder.add_multiplication(out_0, pb_refs[1]).unwrap();
-        let _ = builder.add_addition(out_1, prv_refs[0]).unwrap();
-
-        builder.build().unwrap()
-    }
-
-    fn dummy_params() -> PublicParameters {
-        let degree = 10;
-        let mut rng = test_rng();
-        let params = KZG10::<Bls12_381, UniPoly381>::setup(degree, false, &mut rng).unwrap();
-
-        PublicParameters { kzg_params: params }
-    }
 
     #[test]
     fn test_generate_witness() {
@@ -270,11 +1149,12 @@ mod tests {
         // | 10   |  5   |  50  | 1 |
         // | 50   |  7   |  57  | 0 |
 
-        let circ = simple_circ();
-        let pp = dummy_params();
+        let circ = simple_circuit();
+        let pp = bls_params(48);
         let public_inputs = vec![Fq::from(3), Fq::from(5)];
         let private_inputs = vec![Fq::from(7)];
-        let mut prover = Prover::<Fq>::new(circ, pp, public_inputs, private_inputs);
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &private_inputs).unwrap();
+        let mut prover = Prover::<Fq>::new(circ, pp, inputs).unwrap();
 
         let result = prover.calculate_witness();
         let expected = [3, 7, 10, 10, 5, 50, 50, 7, 57, 7, 5, 3]
@@ -291,12 +1171,13 @@ mod tests {
 
     #[test]
     fn test_trace_polynomial() {
-        let circ = simple_circ();
-        let pp = dummy_params();
+        let circ = simple_circuit();
+        let pp = bls_params(48);
         let size = circ.n_cells().checked_next_power_of_two().unwrap();
         let public_inputs = vec![Fq::from(3), Fq::from(5)];
         let private_inputs = vec![Fq::from(7)];
-        let mut prover = Prover::<Fq>::new(circ, pp, public_inputs, private_inputs);
+        let inputs = InputAssignment::with_values(&circ, &public_inputs, &private_inputs).unwrap();
+        let mut prover = Prover::<Fq>::new(circ, pp, inputs).unwrap();
 
         let _ = prover.calculate_witness();
         let expected = [3, 7, 10, 10, 5, 50, 50, 7, 57, 7, 5, 3]
@@ -317,4 +1198,742 @@ mod tests {
             assert_eq!(*w, val);
         }
     }
+
+    #[test]
+    fn test_generate_witness_mux() {
+        // out = sel ? priv_0 : priv_1
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 2));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let _ = builder
+            .add_mux(pb_refs[0], prv_refs[0], prv_refs[1])
+            .unwrap();
+        let circ = builder.build().unwrap();
+        assert_eq!(circ.n_rows(), 4);
+
+        for (sel, expected) in [(1, 7), (0, 9)] {
+            let public_inputs = vec![Fq::from(sel)];
+            let private_inputs = vec![Fq::from(7), Fq::from(9)];
+            let inputs =
+                InputAssignment::with_values(&circ, &public_inputs, &private_inputs).unwrap();
+            let mut prover = Prover::<Fq>::new(circ.clone(), bls_params(48), inputs).unwrap();
+
+            let result = prover.calculate_witness();
+            assert!(result.is_ok(), "Witness should be correctly calculated");
+
+            let trace = prover.computation_trace.unwrap();
+            assert_eq!(trace[circ.output_id().0], Fq::from(expected));
+        }
+    }
+
+    #[test]
+    fn test_generate_witness_copy_constraints_across_regions() {
+        // Input Cells
+        // | pub_0 | priv_0 |
+        //
+        // Wire Cells
+        // | lhs    | rhs    | out   | s |
+        // |--------|--------|-------|---|
+        // | pub_0  | priv_0 | out_0 | 0 | out_0 is not wired anywhere
+        // | priv_0 | priv_0 | out   | 1 | out is wired to pub_0
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let _ = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
+        let out = builder
+            .add_multiplication(prv_refs[0], prv_refs[0])
+            .unwrap();
+        builder.assert_equals_public(out, 0).unwrap();
+        let circ = builder.build().unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, [Fq::from(4)], &[Fq::from(2)]).unwrap();
+        let mut prover = Prover::<Fq>::new(circ.clone(), bls_params(48), inputs).unwrap();
+        let result = prover.calculate_witness();
+        assert!(result.is_ok(), "Witness should be correctly calculated");
+
+        let expected = [4, 2, 6, 2, 2, 4, 2, 4]
+            .iter()
+            .map(|i| Fq::from(*i))
+            .collect::<Vec<_>>();
+        let trace = prover.computation_trace.unwrap();
+        assert_eq!(trace, expected);
+        assert!(circ.get_copy_constraints(CellId(2)).is_none());
+    }
+
+    #[test]
+    fn test_generate_witness_inputs_out_of_order() {
+        let circ = simple_circuit();
+
+        // assign inputs in arbitrary order
+        let mut inputs = InputAssignment::new(&circ);
+        inputs.set_private(0, Fq::from(7)).unwrap();
+        inputs.set_public(1, Fq::from(5)).unwrap();
+        assert!(inputs.set_public(2, Fq::from(1)).is_err());
+
+        // public input 0 is not assigned yet
+        let result = Prover::<Fq>::new(circ.clone(), bls_params(48), inputs.clone());
+        assert_eq!(
+            format!("{}", result.err().unwrap()),
+            "Public input 0 is not assigned."
+        );
+
+        inputs.set_public(0, Fq::from(3)).unwrap();
+        let mut prover = Prover::<Fq>::new(circ, bls_params(48), inputs).unwrap();
+        prover.calculate_witness().unwrap();
+
+        let expected = [3, 7, 10, 10, 5, 50, 50, 7, 57, 7, 5, 3]
+            .iter()
+            .map(|i| Fq::from(*i))
+            .collect::<Vec<_>>();
+        assert_eq!(prover.computation_trace.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_r1cs_satisfied_by_witness() {
+        // out = sel ? priv_0 : priv_1, wired to pub_1
+        let mut builder = CircuitBuilder::new(InputConfig::new(2, 2));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let out = builder
+            .add_mux(pb_refs[0], prv_refs[0], prv_refs[1])
+            .unwrap();
+        builder.assert_equals_public(out, 1).unwrap();
+        let circ = builder.build().unwrap();
+
+        let (constraints, n_variables) = circ.to_r1cs::<Fq>();
+        assert_eq!(n_variables, circ.n_cells());
+
+        let inputs = InputAssignment::with_values(
+            &circ,
+            [Fq::from(1), Fq::from(7)],
+            &[Fq::from(7), Fq::from(9)],
+        )
+        .unwrap();
+        let mut prover = Prover::<Fq>::new(circ, bls_params(48), inputs).unwrap();
+        prover.calculate_witness().unwrap();
+
+        let mut witness = prover.computation_trace.unwrap();
+        assert!(constraints.iter().all(|c| c.is_satisfied(&witness)));
+
+        // breaking a single cell violates some constraint
+        witness[0] += Fq::from(1);
+        assert!(!constraints.iter().all(|c| c.is_satisfied(&witness)));
+    }
+
+    #[test]
+    fn test_generate_witness_division() {
+        // out = pub_0 / priv_0
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let _ = builder.add_division(pb_refs[0], prv_refs[0]).unwrap();
+        let circ = builder.build().unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, [Fq::from(10)], &[Fq::from(5)]).unwrap();
+        let mut prover = Prover::<Fq>::new(circ.clone(), bls_params(48), inputs).unwrap();
+        let result = prover.calculate_witness();
+        assert!(result.is_ok(), "Witness should be correctly calculated");
+
+        let trace = prover.computation_trace.unwrap();
+        assert_eq!(trace[circ.output_id().0], Fq::from(2));
+
+        // division by zero has no inverse hint
+        let inputs = InputAssignment::with_values(&circ, [Fq::from(10)], &[Fq::from(0)]).unwrap();
+        let mut prover = Prover::<Fq>::new(circ, bls_params(48), inputs).unwrap();
+        let error = prover.calculate_witness().unwrap_err();
+        assert_eq!(format!("{}", error), "Division by zero in row 0.");
+    }
+
+    #[test]
+    fn test_generate_witness_nonzero_constraint() {
+        // out = pub_0 * priv_0 where priv_0 != 0
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        builder.add_nonzero_constraint(prv_refs[0]).unwrap();
+        let _ = builder.add_multiplication(pb_refs[0], prv_refs[0]).unwrap();
+        let circ = builder.build().unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, [Fq::from(10)], &[Fq::from(4)]).unwrap();
+        let mut prover = Prover::<Fq>::new(circ.clone(), bls_params(48), inputs).unwrap();
+        prover.calculate_witness().unwrap();
+
+        let trace = prover.computation_trace.unwrap();
+        assert_eq!(trace[1] * Fq::from(4), Fq::from(1));
+        assert_eq!(trace[circ.output_id().0], Fq::from(40));
+
+        let inputs = InputAssignment::with_values(&circ, [Fq::from(10)], &[Fq::from(0)]).unwrap();
+        let mut prover = Prover::<Fq>::new(circ, bls_params(48), inputs).unwrap();
+        let error = prover.calculate_witness().unwrap_err();
+        assert_eq!(format!("{}", error), "Division by zero in row 0.");
+    }
+
+    #[test]
+    fn test_generate_witness_linear_constraint() {
+        // a = pub_0 * priv_0, b = pub_1 + priv_0, c = priv_0 * priv_0 where a + 2b = c
+        let mut builder = CircuitBuilder::new(InputConfig::new(2, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let a = builder.add_multiplication(pb_refs[0], prv_refs[0]).unwrap();
+        let b = builder.add_addition(pb_refs[1], prv_refs[0]).unwrap();
+        let c = builder
+            .add_multiplication(prv_refs[0], prv_refs[0])
+            .unwrap();
+        builder
+            .add_linear_constraint(&[(Fr::from(1), a), (Fr::from(2), b), (-Fr::from(1), c)])
+            .unwrap();
+        let circ = builder.build().unwrap();
+
+        // 3 * 7 + 2 * (7 + 7) = 7 * 7
+        let public = [Fr::from(3), Fr::from(7)];
+        let inputs = InputAssignment::with_values(&circ, public, &[Fr::from(7)]).unwrap();
+        let mut prover = Prover::<Fr>::new(circ.clone(), bls_params(48), inputs).unwrap();
+        prover.calculate_witness().unwrap();
+
+        let trace = prover.computation_trace.unwrap();
+        assert_eq!(trace[2] + trace[5].double(), trace[8]);
+
+        let public = [Fr::from(3), Fr::from(8)];
+        let inputs = InputAssignment::with_values(&circ, public, &[Fr::from(7)]).unwrap();
+        let mut prover = Prover::<Fr>::new(circ, bls_params(48), inputs).unwrap();
+        assert!(prover.calculate_witness().is_err());
+
+        // pub_0 / 2 - pub_1 = 0, with a coefficient beyond u64
+        let mut builder = CircuitBuilder::new(InputConfig::new(2, 1));
+        let (pb_refs, _) = builder.get_input_refs();
+        let half = Fr::from(2).inverse().unwrap();
+        builder
+            .add_linear_constraint(&[(half, pb_refs[0]), (-Fr::from(1), pb_refs[1])])
+            .unwrap();
+        let circ = builder.build().unwrap();
+
+        for (public, holds) in [
+            ([Fr::from(8), Fr::from(4)], true),
+            ([Fr::from(8), Fr::from(5)], false),
+        ] {
+            let inputs = InputAssignment::with_values(&circ, public, &[Fr::from(1)]).unwrap();
+            let mut prover = Prover::<Fr>::new(circ.clone(), bls_params(48), inputs).unwrap();
+            assert_eq!(prover.calculate_witness().is_ok(), holds);
+        }
+    }
+
+    #[test]
+    fn test_debug_polynomials() {
+        // polynomial multiplication needs FFT friendly field
+        let circ = simple_circuit();
+        let (public, private) = simple_inputs::<Fr>();
+        let inputs = InputAssignment::with_values(&circ, &public, &private).unwrap();
+        let mut prover = Prover::<Fr>::new(circ, bls_params(48), inputs).unwrap();
+
+        let polys = prover.debug_polynomials().unwrap();
+        assert_eq!(&polys.q_gate * &polys.gate_vanishing, polys.gate);
+        assert_eq!(&polys.q_input * &polys.input_vanishing, polys.input);
+        assert_eq!(&polys.trace - &polys.public_input, polys.input);
+    }
+
+    #[test]
+    fn test_gate_identity_on_padding_rows() {
+        // 3 gates and 3 inputs take 12 cells of the domain of size 16
+        let circ = simple_circuit();
+        assert_eq!(circ.padding_rows().collect::<Vec<_>>(), vec![12]);
+
+        let (public, private) = simple_inputs::<Fr>();
+        let inputs = InputAssignment::with_values(&circ, &public, &private).unwrap();
+        let mut prover = Prover::<Fr>::new(circ.clone(), bls_params(48), inputs).unwrap();
+        let polys = prover.debug_polynomials().unwrap();
+
+        let domain = GeneralEvaluationDomain::<Fr>::new(16).unwrap();
+        for id in circ.padding_rows() {
+            for cell in id..id + 3 {
+                assert!(polys.trace.evaluate(&domain.element(cell)).is_zero());
+                assert!(polys.selector.evaluate(&domain.element(cell)).is_zero());
+            }
+            assert!(polys.gate.evaluate(&domain.element(id)).is_zero());
+        }
+
+        // 3 gates and 2 inputs take 11 cells, so the padding row starts at the next row
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let mut out = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
+        for _ in 0..2 {
+            out = builder.add_multiplication(out, prv_refs[0]).unwrap();
+        }
+        let circ = builder.build().unwrap();
+        assert_eq!(circ.n_cells(), 11);
+        assert_eq!(circ.padding_rows().collect::<Vec<_>>(), vec![12]);
+    }
+
+    #[test]
+    fn test_generate_witness_wide_sum() {
+        // out = pub_0 + pub_1 + priv_0 + priv_1 + priv_2
+        let mut builder = CircuitBuilder::new(InputConfig::new(2, 3));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let terms = [
+            pb_refs[0],
+            pb_refs[1],
+            prv_refs[0],
+            prv_refs[1],
+            prv_refs[2],
+        ];
+        let _ = builder.add_wide_sum(&terms).unwrap();
+        let circ = builder.build().unwrap();
+
+        // chained additions would take 4 rows
+        assert_eq!(circ.n_rows(), 3);
+        assert_eq!(circ.gates().count(), 4);
+
+        let inputs = InputAssignment::with_values(
+            &circ,
+            [Fq::from(1), Fq::from(2)],
+            &[Fq::from(3), Fq::from(4), Fq::from(5)],
+        )
+        .unwrap();
+        let mut prover = Prover::<Fq>::new(circ.clone(), bls_params(48), inputs).unwrap();
+        prover.calculate_witness().unwrap();
+
+        let expected = [1, 2, 3, 3, 6, 4, 10, 5, 15, 5, 4, 3, 2, 1]
+            .iter()
+            .map(|i| Fq::from(*i))
+            .collect::<Vec<_>>();
+        let trace = prover.computation_trace.unwrap();
+        assert_eq!(trace, expected);
+
+        let (constraints, _) = circ.to_r1cs::<Fq>();
+        assert!(constraints.iter().all(|c| c.is_satisfied(&trace)));
+    }
+
+    #[test]
+    fn test_commitments_match_serial_commit() {
+        let circ = simple_circuit();
+        let (public, private) = simple_inputs::<Fr>();
+        let inputs = InputAssignment::with_values(&circ, &public, &private).unwrap();
+        let mut prover = Prover::<Fr>::new(circ, bls_params(48), inputs).unwrap();
+        prover.set_blinding_degree(0).unwrap();
+
+        let proof = prover.prove().unwrap();
+        let polys = prover.debug_polynomials().unwrap();
+        let params = &prover.pk.params;
+        assert_eq!(proof.t_comm, Kzg::commit(params, &polys.trace).unwrap());
+        assert_eq!(
+            proof.q_gate_comm,
+            Kzg::commit(params, &polys.q_gate).unwrap()
+        );
+        assert_eq!(
+            proof.q_input_comm,
+            Kzg::commit(params, &polys.q_input).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_cloned_prover_produces_identical_proofs() {
+        let circ = simple_circuit();
+        let (public, private) = simple_inputs::<Fr>();
+        let inputs = InputAssignment::with_values(&circ, &public, &private).unwrap();
+        let pp = bls_params(48);
+        let mut prover = Prover::<Fr>::new(circ.clone(), pp.clone(), inputs).unwrap();
+        prover.calculate_witness().unwrap();
+
+        let mut cloned = prover.clone();
+        assert_eq!(cloned.computation_trace, prover.computation_trace);
+        assert!(!format!("{:?}", cloned).contains("params"));
+
+        // clones share the witness, so the same blinders give the same proof
+        let proof = prover
+            .prove_with_rng(&mut StdRng::seed_from_u64(1))
+            .unwrap();
+        let cloned_proof = cloned
+            .prove_with_rng(&mut StdRng::seed_from_u64(1))
+            .unwrap();
+        assert_eq!(proof.to_bytes(), cloned_proof.to_bytes());
+
+        let mut verifier = Verifier::<Fr>::new(circ, pp, public);
+        assert!(verifier.verify(proof));
+        assert!(verifier.verify(cloned_proof));
+    }
+
+    #[test]
+    fn test_corrupted_witness_is_rejected() {
+        let circ = simple_circuit();
+        let (public, private) = simple_inputs::<Fr>();
+        let inputs = InputAssignment::with_values(&circ, &public, &private).unwrap();
+        let pp = bls_params(48);
+        let mut verifier = Verifier::<Fr>::new(circ.clone(), pp.clone(), public);
+
+        let mut prover = Prover::<Fr>::new(circ.clone(), pp, inputs).unwrap();
+        prover.calculate_witness().unwrap();
+        assert!(verifier.verify(prover.prove().unwrap()));
+
+        // the output gate (3 + 7) * 5 + 7 no longer holds
+        prover.corrupt_witness_cell(circ.output_id(), Fr::from(58));
+        let proof = prover.prove_with_precomputed_witness().unwrap();
+        assert!(!verifier.verify(proof));
+
+        // an honest witness is computed again
+        prover.reset_witness();
+        assert!(verifier.verify(prover.prove().unwrap()));
+    }
+
+    #[test]
+    fn test_cached_selector_polynomial() {
+        let circ = simple_circuit();
+        let (public, private) = simple_inputs::<Fr>();
+        let inputs = InputAssignment::with_values(&circ, &public, &private).unwrap();
+        let mut prover = Prover::<Fr>::new(circ.clone(), bls_params(48), inputs).unwrap();
+        let mut recomputing = prover.clone();
+
+        prover.prove().unwrap();
+        assert_eq!(
+            prover.selector.get(),
+            Some(&compute_selector_polynomial(&circ).unwrap())
+        );
+
+        // the second proof reuses the selector, the clone interpolates it again
+        let seed = |seed| StdRng::seed_from_u64(seed);
+        let cached = prover.prove_with_rng(&mut seed(1)).unwrap();
+        assert!(recomputing.selector.get().is_none());
+        let recomputed = recomputing.prove_with_rng(&mut seed(1)).unwrap();
+        assert_eq!(cached.to_bytes(), recomputed.to_bytes());
+    }
+
+    #[test]
+    fn test_public_inputs_differ_from_setup() {
+        let circ = simple_circuit();
+        let inputs =
+            InputAssignment::with_values(&circ, [Fr::from(3), Fr::from(6)], &[Fr::from(7)])
+                .unwrap();
+        let mut prover = Prover::<Fr>::new(circ, bls_params(48), inputs).unwrap();
+
+        assert_eq!(
+            format!("{}", prover.prove().unwrap_err()),
+            "Public inputs do not match the ones used at setup."
+        );
+    }
+
+    #[test]
+    fn test_input_quotient_exceeds_srs() {
+        let circ = simple_circuit();
+        let (public, private) = simple_inputs::<Fr>();
+        let inputs = InputAssignment::with_values(&circ, &public, &private).unwrap();
+        // SRS smaller than the degree 16 quotient of the input constraint
+        let mut pp = bls_params(48);
+        pp.pk.params = Kzg::setup(8, &mut test_rng()).unwrap();
+        let mut prover = Prover::<Fr>::new(circ, pp, inputs).unwrap();
+
+        assert_eq!(
+            format!("{}", prover.prove().unwrap_err()),
+            "Input quotient of degree 16 exceeds the supported degree 8."
+        );
+    }
+
+    #[test]
+    fn test_prove_with_precomputed_witness() {
+        let circ = simple_circuit();
+        let (public, private) = simple_inputs::<Fr>();
+        let inputs = InputAssignment::with_values(&circ, &public, &private).unwrap();
+        let mut prover = Prover::<Fr>::new(circ, bls_params(48), inputs).unwrap();
+
+        let error = prover.prove_with_precomputed_witness().unwrap_err();
+        assert_eq!(format!("{}", error), "Witness has not been computed.");
+        assert!(prover.computation_trace.is_none());
+
+        prover.calculate_witness().unwrap();
+        assert!(prover.prove_with_precomputed_witness().is_ok());
+    }
+
+    #[test]
+    fn test_progress_callback() {
+        let circ = simple_circuit();
+        let (public, private) = simple_inputs::<Fr>();
+        let inputs = InputAssignment::with_values(&circ, &public, &private).unwrap();
+        let mut prover = Prover::<Fr>::new(circ, bls_params(48), inputs).unwrap();
+
+        let stages = Arc::new(Mutex::new(vec![]));
+        let recorded = stages.clone();
+        prover.set_progress_callback(move |stage| recorded.lock().unwrap().push(stage));
+        prover.prove().unwrap();
+
+        assert_eq!(
+            *stages.lock().unwrap(),
+            vec![
+                ProvingStage::WitnessDone,
+                ProvingStage::TracePolyDone,
+                ProvingStage::Committed,
+                ProvingStage::Opened
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prefix_products_segmented() {
+        let mut rng = test_rng();
+        let factors: Vec<Fr> = (0..37).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut expected = vec![Fr::one()];
+        for (i, factor) in factors.iter().enumerate() {
+            expected.push(expected[i] * factor);
+        }
+
+        // segments of uneven length, and more segments than factors
+        for segments in [1, 2, 3, 8, 64] {
+            assert_eq!(prefix_products(&factors, segments), expected);
+        }
+        assert_eq!(prefix_products::<Fr>(&[], 4), vec![Fr::one()]);
+    }
+
+    #[test]
+    fn test_compute_cell() {
+        // outputs a * b and a + c are computed in independent rows
+        let mut builder = CircuitBuilder::new(InputConfig::new(0, 3));
+        let (_, prv_refs) = builder.get_input_refs();
+        let _ = builder
+            .add_multiplication(prv_refs[0], prv_refs[1])
+            .unwrap();
+        let second = builder.add_addition(prv_refs[0], prv_refs[2]).unwrap();
+        let circ = builder.build().unwrap();
+        let Cellref::Wire(second) = second else {
+            panic!("Output of a gate should be a wire");
+        };
+
+        let private = [Fr::from(3), Fr::from(5), Fr::from(7)];
+        let inputs = InputAssignment::with_values(&circ, [], &private).unwrap();
+        let pp = setup(&circ, &[] as &[Fr], &mut test_rng(), 50).unwrap();
+        let mut prover = Prover::<Fr>::new(circ.clone(), pp, inputs).unwrap();
+        assert_eq!(prover.compute_cell(CellId(second)).unwrap(), Fr::from(10));
+        assert!(prover.computation_trace.is_none());
+
+        let error = prover.compute_cell(CellId(circ.n_cells())).unwrap_err();
+        assert_eq!(
+            format!("{}", error),
+            format!("Cell {} does not exist.", circ.n_cells())
+        );
+
+        prover.calculate_witness().unwrap();
+        assert_eq!(prover.compute_cell(CellId(second)).unwrap(), Fr::from(10));
+        assert_eq!(prover.compute_cell(CellId(2)).unwrap(), Fr::from(15));
+    }
+
+    #[test]
+    fn test_compute_output_by_index() {
+        // outputs a * b and (a + c) * c only share the input a
+        let mut builder = CircuitBuilder::new(InputConfig::new(0, 3));
+        let (_, prv_refs) = builder.get_input_refs();
+        let first = builder
+            .add_multiplication(prv_refs[0], prv_refs[1])
+            .unwrap();
+        let sum = builder.add_addition(prv_refs[0], prv_refs[2]).unwrap();
+        let _ = builder.add_multiplication(sum, prv_refs[2]).unwrap();
+        let circ = builder.build().unwrap();
+        let Cellref::Wire(first) = first else {
+            panic!("Output of a gate should be a wire");
+        };
+        assert_eq!(circ.outputs(), [CellId(first), circ.output_id()]);
+
+        let private = [Fr::from(3), Fr::from(5), Fr::from(7)];
+        let inputs = InputAssignment::with_values(&circ, [], &private).unwrap();
+        let pp = setup(&circ, &[] as &[Fr], &mut test_rng(), 50).unwrap();
+        let prover = Prover::<Fr>::new(circ.clone(), pp, inputs).unwrap();
+        assert_eq!(prover.compute_output(1).unwrap(), Fr::from(70));
+        assert_eq!(prover.compute_output(0).unwrap(), Fr::from(15));
+        assert!(prover.computation_trace.is_none());
+
+        let error = prover.compute_output(2).unwrap_err();
+        assert_eq!(format!("{}", error), "Output 2 does not exist.");
+
+        // the gate of the first output is out of the cone of the second
+        let trace = circ
+            .propagate(&prover.inputs, Some(circ.output_id().0))
+            .unwrap();
+        assert_eq!(trace[circ.output_id().0], Some(Fr::from(70)));
+        assert_eq!(trace[first], None);
+    }
+
+    #[test]
+    fn test_generate_witness_unused_input() {
+        // out = (pub_0 + priv_0) * pub_1, pub_2 is not wired anywhere
+        let mut builder = CircuitBuilder::new(InputConfig::new(3, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let out = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
+        let _ = builder.add_multiplication(out, pb_refs[1]).unwrap();
+        let circ = builder.build().unwrap();
+        assert_eq!(circ.unused_inputs(), vec![2]);
+
+        let public = [Fr::from(3), Fr::from(5), Fr::from(11)];
+        let inputs = InputAssignment::with_values(&circ, public, &[Fr::from(7)]).unwrap();
+        let pp = setup(&circ, public, &mut test_rng(), 50).unwrap();
+        let mut prover = Prover::<Fr>::new(circ.clone(), pp, inputs).unwrap();
+        prover.calculate_witness().unwrap();
+
+        // the unused input cell is still filled
+        let trace = prover.computation_trace.unwrap();
+        assert_eq!(trace[circ.n_cells() - 3], Fr::from(11));
+        assert_eq!(trace[circ.output_id().0], Fr::from(50));
+    }
+
+    #[test]
+    fn test_generate_witness_near_modulus() {
+        let circ = simple_circuit();
+        let mut minus_one = Fr::MODULUS;
+        minus_one.sub_with_borrow(&BigInt::from(1u64));
+
+        let inputs = InputAssignment::<Fr>::with_bigints(
+            &circ,
+            &[minus_one, BigInt::from(5u64)],
+            &[BigInt::from(7u64)],
+        )
+        .unwrap();
+        let mut prover = Prover::<Fr>::new(circ.clone(), bls_params(48), inputs).unwrap();
+        prover.calculate_witness().unwrap();
+
+        // out = (-1 + 7) * 5 + 7
+        let trace = prover.computation_trace.clone().unwrap();
+        let n_cells = circ.n_cells();
+        assert_eq!(trace[n_cells - 1], -Fr::from(1));
+        assert_eq!(trace[circ.output_id().0], Fr::from(37));
+
+        let poly = prover.public_input_encoder.encode(prover.public_input());
+        let domain = GeneralEvaluationDomain::<Fr>::new(n_cells.next_power_of_two()).unwrap();
+        assert_eq!(poly.evaluate(&domain.element(n_cells - 1)), -Fr::from(1));
+
+        // the modulus itself is not a field element
+        let result = InputAssignment::<Fr>::with_bigints(
+            &circ,
+            &[Fr::MODULUS, BigInt::from(5u64)],
+            &[BigInt::from(7u64)],
+        );
+        assert_eq!(
+            format!("{}", result.unwrap_err()),
+            "Public input 0 is not smaller than the modulus."
+        );
+    }
+
+    #[test]
+    fn test_compute_output() {
+        let circ = simple_circuit();
+        let (public, private) = simple_inputs::<Fq>();
+        assert_eq!(
+            circ.compute_output(&public, &private).unwrap(),
+            Fq::from(57)
+        );
+
+        let inputs = InputAssignment::with_values(&circ, &public, &private).unwrap();
+        let mut prover = Prover::<Fq>::new(circ.clone(), bls_params(48), inputs).unwrap();
+        prover.calculate_witness().unwrap();
+        let trace = prover.computation_trace.unwrap();
+        assert_eq!(trace[circ.output_id().0], Fq::from(57));
+
+        assert!(circ.compute_output(&public, &[]).is_err());
+    }
+
+    #[test]
+    fn test_generate_witness_shuffled_gates() {
+        // out = ((pub_0 + priv_0) * pub_1 + priv_0) * pub_1
+        let mut builder = CircuitBuilder::new(InputConfig::new(2, 1));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let out_0 = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
+        let out_1 = builder.add_multiplication(out_0, pb_refs[1]).unwrap();
+        let out_2 = builder.add_addition(out_1, prv_refs[0]).unwrap();
+        let _ = builder.add_multiplication(out_2, pb_refs[1]).unwrap();
+        let circ = builder.build().unwrap();
+
+        // define the gates in reverse order, each gate's operands come from a later gate
+        let n_rows = circ.n_rows();
+        let remap = |id: usize| {
+            if id < 3 * n_rows {
+                3 * (n_rows - 1 - id / 3) + id % 3
+            } else {
+                id
+            }
+        };
+        let mut shuffled = circ.clone();
+        shuffled.selectors.reverse();
+        shuffled.gates = circ
+            .gates
+            .iter()
+            .rev()
+            .map(|(id, op)| (remap(*id), *op))
+            .collect();
+        shuffled.copy_constraints = circ
+            .copy_constraints
+            .iter()
+            .map(|class| class.iter().map(|id| remap(*id)).collect())
+            .collect();
+
+        let trace = |circ: Circuit| {
+            let inputs =
+                InputAssignment::with_values(&circ, [Fq::from(3), Fq::from(5)], &[Fq::from(7)])
+                    .unwrap();
+            let mut prover = Prover::<Fq>::new(circ, bls_params(48), inputs).unwrap();
+            prover.calculate_witness().unwrap();
+            prover.computation_trace.unwrap()
+        };
+        let expected = trace(circ);
+        let actual = trace(shuffled);
+        assert_eq!(expected[11], Fq::from(285));
+        for (id, value) in expected.iter().enumerate() {
+            assert_eq!(actual[remap(id)], *value);
+        }
+    }
+
+    #[test]
+    fn test_generate_witness_cyclic_dependency() {
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 0));
+        let (pb_refs, _) = builder.get_input_refs();
+        let _ = builder.add_addition(pb_refs[0], pb_refs[0]).unwrap();
+        let mut circ = builder.build().unwrap();
+        // lhs of the gate is wired to its own output
+        circ.copy_constraints = vec![vec![0, 2], vec![1, 3]];
+
+        let inputs = InputAssignment::with_values(&circ, [Fq::from(3)], &[]).unwrap();
+        let mut prover = Prover::<Fq>::new(circ, bls_params(48), inputs).unwrap();
+        let error = prover.calculate_witness().unwrap_err();
+        assert_eq!(
+            format!("{}", error),
+            "Cyclic dependency among cells [0, 2]."
+        );
+    }
+
+    #[test]
+    fn test_generate_witness_rejects_violations() {
+        // out_1 = out_0 + pub_0 can't be equal to out_0 unless pub_0 is zero
+        let mut builder = CircuitBuilder::new(InputConfig::new(1, 0));
+        let (pb_refs, _) = builder.get_input_refs();
+        let out_0 = builder.add_addition(pb_refs[0], pb_refs[0]).unwrap();
+        let out_1 = builder.add_addition(out_0, pb_refs[0]).unwrap();
+        builder.add_wire_constraint(out_0, out_1).unwrap();
+        let circ = builder.build().unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, [Fq::from(3)], &[]).unwrap();
+        let mut prover = Prover::<Fq>::new(circ, bls_params(48), inputs).unwrap();
+        let error = prover.calculate_witness().unwrap_err();
+        assert_eq!(
+            format!("{}", error),
+            "Gate at cell 3 in row 1 is not satisfied."
+        );
+        assert!(prover.computation_trace.is_none());
+
+        // output wired to a public input which doesn't match the computation
+        let mut builder = CircuitBuilder::new(InputConfig::new(2, 0));
+        let (pb_refs, _) = builder.get_input_refs();
+        let out = builder.add_addition(pb_refs[0], pb_refs[0]).unwrap();
+        builder.assert_equals_public(out, 1).unwrap();
+        let circ = builder.build().unwrap();
+
+        let inputs = InputAssignment::with_values(&circ, [Fq::from(3), Fq::from(7)], &[]).unwrap();
+        let mut prover = Prover::<Fq>::new(circ, bls_params(48), inputs).unwrap();
+        let error = prover.calculate_witness().unwrap_err();
+        assert_eq!(
+            format!("{}", error),
+            "Gate at cell 0 in row 0 is not satisfied."
+        );
+
+        // two public inputs wired together hold different values
+        let mut builder = CircuitBuilder::new(InputConfig::new(2, 0));
+        let (pb_refs, _) = builder.get_input_refs();
+        let _ = builder.add_addition(pb_refs[0], pb_refs[1]).unwrap();
+        builder.add_wire_constraint(pb_refs[0], pb_refs[1]).unwrap();
+        let circ = builder.build().unwrap();
+        let class = circ.copy_constraint_class(0).unwrap().to_vec();
+
+        let inputs = InputAssignment::with_values(&circ, [Fq::from(3), Fq::from(7)], &[]).unwrap();
+        let mut prover = Prover::<Fq>::new(circ, bls_params(48), inputs).unwrap();
+        let error = prover.calculate_witness().unwrap_err();
+        assert_eq!(
+            format!("{}", error),
+            format!("Copy constraint class {:?} holds different values.", class)
+        );
+    }
 }
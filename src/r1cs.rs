@@ -0,0 +1,39 @@
+use ark_ff::Field;
+
+/// Variable of a rank-1 constraint system.
+/// Each cell of the circuit is a variable, `One` is the constant 1.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Variable {
+    One,
+    Cell(usize),
+}
+
+/// Linear combination of variables with their coefficients.
+pub type LinearCombination<F> = Vec<(F, Variable)>;
+
+/// Rank-1 constraint `⟨a, z⟩ ⋅ ⟨b, z⟩ = ⟨c, z⟩`, where z is the assignment of variables.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Constraint<F: Field> {
+    pub a: LinearCombination<F>,
+    pub b: LinearCombination<F>,
+    pub c: LinearCombination<F>,
+}
+
+impl<F: Field> Constraint<F> {
+    /// Check the constraint is satisfied by `witness`, which holds the value of each cell.
+    pub fn is_satisfied(&self, witness: &[F]) -> bool {
+        let eval = |lc: &LinearCombination<F>| {
+            lc.iter()
+                .map(|(coeff, var)| match var {
+                    Variable::One => Some(*coeff),
+                    Variable::Cell(id) => witness.get(*id).map(|x| *coeff * x),
+                })
+                .sum::<Option<F>>()
+        };
+
+        match (eval(&self.a), eval(&self.b), eval(&self.c)) {
+            (Some(a), Some(b), Some(c)) => a * b == c,
+            _ => false,
+        }
+    }
+}
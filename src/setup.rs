@@ -1,29 +1,26 @@
-use ark_bls12_381::Bls12_381;
-use ark_ec::pairing::Pairing;
+use ark_bls12_381::Fr;
 use ark_ff::{FftField, PrimeField};
-use ark_poly::{
-    univariate::DensePolynomial, EvaluationDomain, Evaluations, GeneralEvaluationDomain,
-};
-use ark_poly_commit::kzg10::KZG10;
 use ark_std::rand::RngCore;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 use crate::{
-    common::{compute_public_input_polynomial, compute_selector_polynomial},
-    types::{PublicParameters, UniPoly381},
+    common::{compute_selector_polynomial, compute_wire_rotation_polynomial},
+    pcs::{CommitmentScheme, Kzg},
+    transcript::public_inputs_digest,
+    types::{ProverKey, PublicParameters, VerificationKey},
     Circuit,
 };
 
 /// setup public parameters
 ///
 /// * `circ` - Circuit to prove.
-/// * `public_input` - Public input to a circuit.
+/// * `public_input` - Public input to a circuit, e.g. `PublicInputValues` checked beforehand.
 /// * `rng` - random number generator used to setup KZG
-/// * `degree` - Maximum degree of KZG.
+/// * `degree` - Maximum degree of KZG, at least `circ.degree_bound()`.
 pub fn setup<F, R>(
     circ: &Circuit,
-    public_input: &[F],
+    public_input: impl AsRef<[F]>,
     rng: &mut R,
     degree: usize,
 ) -> Result<PublicParameters>
@@ -31,15 +28,111 @@ where
     F: FftField + PrimeField,
     R: RngCore,
 {
-    let v_poly = compute_public_input_polynomial(circ, public_input);
-    let s_poly = compute_selector_polynomial::<F>(circ);
+    setup_with_scheme::<Kzg, F, R>(circ, public_input, rng, degree)
+}
 
-    // testing copy constraints by permutation argument
+/// setup public parameters with the given polynomial commitment scheme
+///
+/// * `circ` - Circuit to prove.
+/// * `public_input` - Public input to a circuit, e.g. `PublicInputValues` checked beforehand.
+/// * `rng` - random number generator used to setup the commitment scheme
+/// * `degree` - Maximum degree of committed polynomials, at least `circ.degree_bound()`.
+pub fn setup_with_scheme<S, F, R>(
+    circ: &Circuit,
+    public_input: impl AsRef<[F]>,
+    rng: &mut R,
+    degree: usize,
+) -> Result<PublicParameters<S>>
+where
+    S: CommitmentScheme,
+    F: FftField + PrimeField,
+    R: RngCore,
+{
+    let public_input = public_input.as_ref();
+    check_public_input_count(circ, public_input)?;
+    if degree < circ.degree_bound() {
+        return Err(anyhow!(
+            "Degree {} is smaller than the degree {} needed by the circuit.",
+            degree,
+            circ.degree_bound()
+        ));
+    }
 
     // Setup poly commit
+    let params = S::setup(degree, rng)?;
+    preprocess(circ, public_input, params)
+}
+
+impl<S: CommitmentScheme> PublicParameters<S> {
+    /// Reuse the SRS of these parameters for another circuit with its public inputs.
+    /// Only the verification key is computed again, so a single setup can serve
+    /// every circuit supported by `supports_circuit`.
+    pub fn for_circuit<F: FftField + PrimeField>(
+        &self,
+        circ: &Circuit,
+        public_input: impl AsRef<[F]>,
+    ) -> Result<PublicParameters<S>> {
+        let public_input = public_input.as_ref();
+        check_public_input_count(circ, public_input)?;
+        if !self.supports_circuit(circ) {
+            return Err(anyhow!(
+                "Public parameters support degree {}, but the circuit needs degree {}.",
+                S::max_degree(&self.pk.params),
+                PublicParameters::<S>::required_degree(circ)
+            ));
+        }
+        preprocess(circ, public_input, self.pk.params.clone())
+    }
+}
 
-    let params = KZG10::<Bls12_381, UniPoly381>::setup(degree, false, rng)?;
-    let pp = PublicParameters { kzg_params: params };
+fn check_public_input_count<F>(circ: &Circuit, public_input: &[F]) -> Result<()> {
+    if public_input.len() != circ.input_config.n_pub() {
+        return Err(anyhow!(
+            "Expected {} public inputs, got {}.",
+            circ.input_config.n_pub(),
+            public_input.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Compute the keys of `circ` over the given commitment parameters.
+/// The prover key records a hash of the public inputs to detect a mismatch when proving.
+fn preprocess<S: CommitmentScheme, F: PrimeField>(
+    circ: &Circuit,
+    public_input: &[F],
+    params: S::Params,
+) -> Result<PublicParameters<S>> {
+    let pcs_vk = S::verifier_params(&params);
+
+    let mut pp = PublicParameters {
+        pk: ProverKey {
+            params,
+            public_inputs_digest: public_inputs_digest(public_input),
+        },
+        vk: VerificationKey {
+            domain_size: circ.n_cells().checked_next_power_of_two().unwrap(),
+            n_cells: circ.n_cells(),
+            n_rows: circ.n_rows(),
+            gate_ids: circ.gates().map(|gate| gate.lhs).collect(),
+            n_pub: circ.input_config.n_pub(),
+            output_id: circ.output_id().0,
+            constants: circ
+                .constant_cells()
+                .map(|(id, value)| (id, value.to_field()))
+                .collect(),
+            s_comm: Default::default(),
+            sigma_comm: Default::default(),
+            pcs_vk,
+        },
+    };
+
+    // commit to selector polynomial and wire rotation polynomial for the verifier
+    let s_poly = compute_selector_polynomial::<Fr>(circ)?;
+    let sigma_poly = compute_wire_rotation_polynomial::<Fr>(circ)?;
+
+    pp.vk.s_comm = S::commit(&pp.pk.params, &s_poly)?;
+    pp.vk.sigma_comm = S::commit(&pp.pk.params, &sigma_poly)?;
 
     Ok(pp)
 }
@@ -47,24 +140,13 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::circuit::{Circuit, CircuitBuilder, InputConfig};
-    use ark_bls12_381::Fq;
-    use ark_poly::{
-        univariate::DensePolynomial, DenseUVPolynomial, EvaluationDomain, GeneralEvaluationDomain,
-        Polynomial,
+    use crate::{
+        circuit::{CircuitBuilder, InputConfig, Op},
+        common::{compute_public_input_polynomial, compute_wire_rotation_polynomial},
+        test_util::simple_circuit,
     };
-
-    // build circuit to calculate
-    // out = (pub_0 + priv_0) * pub_1 + priv_0
-    fn simple_circ() -> Circuit {
-        let mut builder = CircuitBuilder::new(InputConfig::new(2, 1));
-        let (pb_refs, prv_refs) = builder.get_input_refs();
-        let out_0 = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
-        let out_1 = builder.add_multiplication(out_0, pb_refs[1]).unwrap();
-        let _ = builder.add_addition(out_1, prv_refs[0]).unwrap();
-
-        builder.build().unwrap()
-    }
+    use ark_bls12_381::Fq;
+    use ark_poly::{EvaluationDomain, Evaluations, GeneralEvaluationDomain, Polynomial};
 
     #[test]
     fn test_compute_public_input_polynomial() {
@@ -73,7 +155,7 @@ mod tests {
         // 𝜔^-i where i∈{1,..,n_pub}
         // 𝜔 is n-th root of unity where n is total number of cells in a circuit.
 
-        let circ = simple_circ();
+        let circ = simple_circuit();
         let public_input = vec![Fq::from(3), Fq::from(5)];
 
         let n_cells = circ.n_cells();
@@ -94,9 +176,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_setup_rejects_small_degree() {
+        let circ = simple_circuit();
+        let public_input = [Fr::from(3), Fr::from(5)];
+        let mut rng = ark_std::test_rng();
+        assert_eq!(circ.degree_bound(), 48);
+
+        let error = setup(&circ, public_input, &mut rng, 2).err().unwrap();
+        assert_eq!(
+            format!("{}", error),
+            "Degree 2 is smaller than the degree 48 needed by the circuit."
+        );
+        assert!(setup(&circ, public_input, &mut rng, 48).is_ok());
+    }
+
     #[test]
     fn test_compute_selector_polynomial() {
-        let circ = simple_circ();
+        let circ = simple_circuit();
 
         let poly = compute_selector_polynomial(&circ).unwrap();
         let domain_size = circ.n_cells().checked_next_power_of_two().unwrap();
@@ -108,5 +205,47 @@ mod tests {
             let val = poly.evaluate(&d);
             assert_eq!(v, val);
         }
+
+        // incrementally tracked selector matches the one computed from the gates
+        let mut builder = CircuitBuilder::new(InputConfig::new(2, 3));
+        let (pb_refs, prv_refs) = builder.get_input_refs();
+        let out = builder.add_multiplication(pb_refs[0], prv_refs[0]).unwrap();
+        let out = builder
+            .add_wide_sum(&[out, pb_refs[1], prv_refs[1]])
+            .unwrap();
+        let _ = builder.add_mux(prv_refs[2], out, pb_refs[0]).unwrap();
+        let circ = builder.build().unwrap();
+
+        let mut evals = vec![Fr::from(0); circ.n_rows() * 3];
+        for gate in circ.gates().filter(|gate| gate.op == Op::Add) {
+            evals[gate.lhs] = Fr::from(1);
+        }
+        let domain_size = circ.n_cells().checked_next_power_of_two().unwrap();
+        let domain = GeneralEvaluationDomain::<Fr>::new(domain_size).unwrap();
+        assert_eq!(
+            compute_selector_polynomial(&circ).unwrap(),
+            Evaluations::from_vec_and_domain(evals, domain).interpolate()
+        );
+
+        let mut empty = simple_circuit();
+        empty.gates.clear();
+        empty.selectors.clear();
+        let error = compute_selector_polynomial::<Fq>(&empty).unwrap_err();
+        assert_eq!(format!("{}", error), "Circuit has no gates.");
+    }
+
+    #[test]
+    fn test_compute_wire_rotation_polynomial() {
+        let circ = simple_circuit();
+
+        let poly = compute_wire_rotation_polynomial(&circ).unwrap();
+        let domain_size = circ.n_cells().checked_next_power_of_two().unwrap();
+        let domain = GeneralEvaluationDomain::<Fq>::new(domain_size).unwrap();
+
+        // copy constraint class [1, 7, 9] is rotated, output cell 8 is not wired.
+        for (from, to) in [(1, 7), (7, 9), (9, 1), (8, 8)] {
+            let val = poly.evaluate(&domain.element(from));
+            assert_eq!(domain.element(to), val);
+        }
     }
 }
@@ -0,0 +1,35 @@
+//! Helpers shared by the tests of each module.
+
+use ark_bls12_381::Fr;
+use ark_ff::Field;
+use ark_std::test_rng;
+
+use crate::{
+    circuit::{Circuit, CircuitBuilder, InputConfig},
+    setup::setup,
+    types::PublicParameters,
+};
+
+/// Circuit calculating
+/// out = (pub_0 + priv_0) * pub_1 + priv_0
+/// with 2 public inputs and 1 private input.
+pub(crate) fn simple_circuit() -> Circuit {
+    let mut builder = CircuitBuilder::new(InputConfig::new(2, 1));
+    let (pb_refs, prv_refs) = builder.get_input_refs();
+    let out_0 = builder.add_addition(pb_refs[0], prv_refs[0]).unwrap();
+    let out_1 = builder.add_multiplication(out_0, pb_refs[1]).unwrap();
+    let _ = builder.add_addition(out_1, prv_refs[0]).unwrap();
+
+    builder.build().unwrap()
+}
+
+/// Public inputs [3, 5] and private input [7] of the simple circuit, whose output is 57.
+pub(crate) fn simple_inputs<F: Field>() -> (Vec<F>, Vec<F>) {
+    (vec![F::from(3u64), F::from(5u64)], vec![F::from(7u64)])
+}
+
+/// Public parameters of the simple circuit over BLS12-381 for polynomials up to `degree`.
+pub(crate) fn bls_params(degree: usize) -> PublicParameters {
+    let (public_inputs, _) = simple_inputs::<Fr>();
+    setup(&simple_circuit(), &public_inputs, &mut test_rng(), degree).unwrap()
+}
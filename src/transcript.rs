@@ -0,0 +1,282 @@
+use ark_bls12_381::Fr;
+use ark_crypto_primitives::sponge::{poseidon::PoseidonSponge, CryptographicSponge};
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+use sha3::{Digest, Keccak256};
+
+use crate::common::{default_poseidon_config, field_to_be_bytes};
+use crate::extension::Fr2;
+
+/// Hash function deriving the Fiat-Shamir challenges.
+/// Prover and verifier have to use the same hash, otherwise the challenges differ.
+pub trait TranscriptHash: Clone {
+    /// Create the initial hash state.
+    fn new() -> Self;
+
+    /// Absorb raw bytes.
+    fn absorb_bytes(&mut self, bytes: &[u8]);
+
+    /// Absorb a field element.
+    fn absorb_scalar(&mut self, scalar: &Fr);
+
+    /// Squeeze a field element.
+    fn squeeze(&mut self) -> Fr;
+}
+
+/// Poseidon sponge over Fr. This is the default transcript hash.
+#[derive(Clone)]
+pub struct Poseidon {
+    sponge: PoseidonSponge<Fr>,
+}
+
+impl TranscriptHash for Poseidon {
+    fn new() -> Self {
+        Self {
+            sponge: PoseidonSponge::new(&default_poseidon_config()),
+        }
+    }
+
+    fn absorb_bytes(&mut self, bytes: &[u8]) {
+        self.sponge.absorb(&bytes.to_vec());
+    }
+
+    fn absorb_scalar(&mut self, scalar: &Fr) {
+        self.sponge.absorb(scalar);
+    }
+
+    fn squeeze(&mut self) -> Fr {
+        self.sponge.squeeze_field_elements(1)[0]
+    }
+}
+
+/// Keccak-256 based transcript.
+/// Scalars are absorbed as 32 byte big-endian integers and challenges are the digest
+/// read as a big-endian integer modulo r, like `uint256(keccak256(..)) % r` in Solidity.
+/// Commitments are absorbed in their compressed arkworks encoding.
+/// Each squeeze hashes the absorbed bytes and restarts the hash from the digest.
+#[derive(Clone, Default)]
+pub struct Keccak {
+    hasher: Keccak256,
+}
+
+impl TranscriptHash for Keccak {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn absorb_bytes(&mut self, bytes: &[u8]) {
+        self.hasher.update(bytes);
+    }
+
+    fn absorb_scalar(&mut self, scalar: &Fr) {
+        self.hasher.update(field_to_be_bytes(scalar));
+    }
+
+    fn squeeze(&mut self) -> Fr {
+        let digest = self.hasher.finalize_reset();
+        self.hasher.update(digest);
+        Fr::from_be_bytes_mod_order(&digest)
+    }
+}
+
+/// Value absorbed into or squeezed out of a transcript, recorded for debugging.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TranscriptEntry {
+    /// Kind of the absorbed value and its bytes.
+    Absorb(&'static str, Vec<u8>),
+    /// Kind of the squeezed value and the challenge.
+    Squeeze(&'static str, Fr),
+}
+
+/// Fiat-Shamir transcript shared by prover and verifier.
+/// Both parties have to absorb the same values in the same order to derive the same challenges.
+pub(crate) struct Transcript<H: TranscriptHash = Poseidon> {
+    hash: H,
+    /// Entries recorded only by a transcript created with `with_log`.
+    log: Option<Vec<TranscriptEntry>>,
+}
+
+impl<H: TranscriptHash> Transcript<H> {
+    /// Create new transcript.
+    pub(crate) fn new() -> Self {
+        Self::create(None)
+    }
+
+    /// Create new transcript recording every absorbed value and squeezed challenge.
+    pub(crate) fn with_log() -> Self {
+        Self::create(Some(vec![]))
+    }
+
+    fn create(log: Option<Vec<TranscriptEntry>>) -> Self {
+        let mut transcript = Self {
+            hash: H::new(),
+            log,
+        };
+        transcript.absorb_bytes("label", b"miniplonk");
+        transcript
+    }
+
+    /// Returns the recorded entries, empty unless created with `with_log`.
+    pub(crate) fn log(&self) -> &[TranscriptEntry] {
+        self.log.as_deref().unwrap_or(&[])
+    }
+
+    fn absorb_bytes(&mut self, label: &'static str, bytes: &[u8]) {
+        if let Some(log) = &mut self.log {
+            log.push(TranscriptEntry::Absorb(label, bytes.to_vec()));
+        }
+        self.hash.absorb_bytes(bytes);
+    }
+
+    /// Absorb public inputs in canonical little-endian byte form.
+    /// The number of inputs is absorbed first, so an empty slice still advances the sponge.
+    pub(crate) fn append_public_inputs<F: CanonicalSerialize>(&mut self, public_inputs: &[F]) {
+        let mut bytes = (public_inputs.len() as u64).to_le_bytes().to_vec();
+        for input in public_inputs {
+            input
+                .serialize_compressed(&mut bytes)
+                .expect("Serializing into vec should not fail");
+        }
+        self.absorb_bytes("public_inputs", &bytes);
+    }
+
+    /// Absorb metadata bytes of a proof, prefixed by their length.
+    pub(crate) fn append_metadata(&mut self, metadata: &[u8]) {
+        let mut bytes = (metadata.len() as u64).to_le_bytes().to_vec();
+        bytes.extend_from_slice(metadata);
+        self.absorb_bytes("metadata", &bytes);
+    }
+
+    /// Absorb a field element.
+    pub(crate) fn append_scalar(&mut self, scalar: &Fr) {
+        if let Some(log) = &mut self.log {
+            let mut bytes = vec![];
+            scalar
+                .serialize_compressed(&mut bytes)
+                .expect("Serializing into vec should not fail");
+            log.push(TranscriptEntry::Absorb("scalar", bytes));
+        }
+        self.hash.absorb_scalar(scalar);
+    }
+
+    /// Absorb an element of the extension field as its two coefficients.
+    pub(crate) fn append_extension_scalar(&mut self, scalar: &Fr2) {
+        self.append_scalar(&scalar.c0);
+        self.append_scalar(&scalar.c1);
+    }
+
+    /// Absorb polynomial commitment.
+    pub(crate) fn append_commitment(&mut self, comm: &impl CanonicalSerialize) {
+        let mut bytes = vec![];
+        comm.serialize_compressed(&mut bytes)
+            .expect("Serializing into vec should not fail");
+        self.absorb_bytes("commitment", &bytes);
+    }
+
+    /// Squeeze a challenge out of the transcript.
+    pub(crate) fn squeeze_challenge(&mut self) -> Fr {
+        let challenge = self.hash.squeeze();
+        if let Some(log) = &mut self.log {
+            log.push(TranscriptEntry::Squeeze("challenge", challenge));
+        }
+        challenge
+    }
+
+    /// Absorb public inputs, metadata and commitments of a proof in the order they are sent,
+    /// returning the challenges β, γ and α squeezed in between.
+    pub(crate) fn replay_commitments<F: CanonicalSerialize, C: CanonicalSerialize>(
+        &mut self,
+        public_inputs: &[F],
+        metadata: &[u8],
+        t_comm: &C,
+        z_comm: &C,
+        quotient_comms: [&C; 3],
+    ) -> (Fr, Fr, Fr) {
+        self.append_public_inputs(public_inputs);
+        self.append_metadata(metadata);
+        self.append_commitment(t_comm);
+        let beta = self.squeeze_challenge();
+        let gamma = self.squeeze_challenge();
+        self.append_commitment(z_comm);
+        let alpha = self.squeeze_challenge();
+        quotient_comms
+            .iter()
+            .for_each(|comm| self.append_commitment(*comm));
+
+        (beta, gamma, alpha)
+    }
+
+    /// Squeeze a challenge in the extension field out of the transcript.
+    pub(crate) fn squeeze_extension_challenge(&mut self) -> Fr2 {
+        let c0 = self.squeeze_challenge();
+        let c1 = self.squeeze_challenge();
+        Fr2::new(c0, c1)
+    }
+}
+
+/// Hash of public inputs, binding the public parameters to the inputs given at setup.
+pub(crate) fn public_inputs_digest<F: CanonicalSerialize>(public_inputs: &[F]) -> Fr {
+    let mut transcript = Transcript::<Poseidon>::new();
+    transcript.append_public_inputs(public_inputs);
+    transcript.squeeze_challenge()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_poseidon_config() {
+        let absorb_and_squeeze = |sponge: &mut PoseidonSponge<Fr>| {
+            sponge.absorb(&Fr::from(3));
+            sponge.absorb(&vec![1u8, 2, 3]);
+            sponge.squeeze_field_elements::<Fr>(2)
+        };
+
+        // prover and verifier build the same parameters independently
+        let mut prover = PoseidonSponge::new(&default_poseidon_config());
+        let mut verifier = PoseidonSponge::new(&default_poseidon_config());
+        let challenges = absorb_and_squeeze(&mut prover);
+        assert_eq!(challenges, absorb_and_squeeze(&mut verifier));
+
+        // the transcript hash uses them too
+        let mut hash = Poseidon::new();
+        hash.absorb_scalar(&Fr::from(3));
+        hash.absorb_bytes(&[1, 2, 3]);
+        assert_eq!(hash.squeeze(), challenges[0]);
+    }
+
+    #[test]
+    fn test_keccak_big_endian() {
+        let mut hash = Keccak::new();
+        hash.absorb_scalar(&Fr::from(258));
+        let mut bytes = [0u8; 32];
+        bytes[30..].copy_from_slice(&[1, 2]);
+        let digest = Keccak256::digest(bytes);
+        assert_eq!(hash.squeeze(), Fr::from_be_bytes_mod_order(&digest));
+    }
+
+    #[test]
+    fn test_public_inputs_challenges() {
+        let challenge = |public_inputs: Option<&[Fr]>| {
+            let mut transcript = Transcript::<Poseidon>::new();
+            if let Some(public_inputs) = public_inputs {
+                transcript.append_public_inputs(public_inputs);
+            }
+            transcript.squeeze_challenge()
+        };
+
+        // prover and verifier derive the same challenges
+        let inputs = [Fr::from(3), Fr::from(5)];
+        assert_eq!(challenge(Some(&inputs)), challenge(Some(&inputs)));
+        assert_eq!(challenge(Some(&[])), challenge(Some(&[])));
+
+        // empty inputs still advance the sponge
+        assert_ne!(challenge(Some(&[])), challenge(None));
+        assert_ne!(challenge(Some(&[])), challenge(Some(&inputs)));
+        assert_ne!(
+            challenge(Some(&inputs)),
+            challenge(Some(&[Fr::from(5), Fr::from(3)]))
+        );
+    }
+}
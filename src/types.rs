@@ -1,13 +1,558 @@
-use ark_bls12_381::Bls12_381;
+use anyhow::{anyhow, Result};
+use ark_bls12_381::{Bls12_381, Fq, Fr, G1Affine, G2Affine};
 use ark_ec::pairing::Pairing;
+use ark_ff::{BigInteger, FftField, PrimeField};
 use ark_poly::univariate::DensePolynomial;
-use ark_poly_commit::kzg10::UniversalParams;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
 
-pub struct Proof {}
+use crate::{
+    circuit::Circuit,
+    extension::Fr2,
+    pcs::{CommitmentScheme, Kzg},
+    transcript::{Poseidon, Transcript, TranscriptHash},
+};
 
+/// Version of the proof format written by `Proof::to_bytes`.
+pub const PROOF_VERSION: u8 = 4;
+
+/// Proof that the prover knows a witness satisfying the circuit.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Proof<S: CommitmentScheme = Kzg> {
+    /// Size of the evaluation domain the proof was generated over.
+    pub(crate) domain_size: usize,
+    /// Commitment to the trace polynomial T.
+    pub(crate) t_comm: S::Commitment,
+    /// Commitment to the permutation accumulator polynomial Z.
+    pub(crate) z_comm: S::Commitment,
+    /// Commitment to the quotient of the gate constraint.
+    pub(crate) q_gate_comm: S::Commitment,
+    /// Commitment to the quotient of the input constraint.
+    pub(crate) q_input_comm: S::Commitment,
+    /// Commitment to the quotient of the permutation constraint.
+    pub(crate) q_perm_comm: S::Commitment,
+
+    pub(crate) evaluations: ProofEvaluations,
+    pub(crate) openings: Openings<S>,
+
+    /// Output value claimed by the prover with its opening of T at the output cell.
+    pub(crate) output_claim: Option<OutputClaim<S>>,
+
+    /// Metadata bound into the transcript before the first challenge.
+    pub(crate) metadata: Vec<u8>,
+
+    /// Cells revealed by the prover with their openings of T.
+    pub(crate) revealed_cells: Vec<RevealedCell<S>>,
+}
+
+impl<S: CommitmentScheme> Proof<S> {
+    /// Returns the output value embedded in the proof, if any.
+    pub fn claimed_output(&self) -> Option<Fr> {
+        self.output_claim.as_ref().map(|claim| claim.value)
+    }
+
+    /// Returns the size of the evaluation domain.
+    pub fn domain_size(&self) -> usize {
+        self.domain_size
+    }
+
+    /// Returns the metadata embedded by `Prover::with_metadata`.
+    pub fn metadata(&self) -> &[u8] {
+        &self.metadata
+    }
+
+    /// Returns the Fiat-Shamir challenges of the proof with the default Poseidon transcript,
+    /// see `challenges_with_hash`.
+    pub fn challenges(&self, public_inputs: &[Fr]) -> Vec<Fr> {
+        self.challenges_with_hash::<Poseidon>(public_inputs)
+    }
+
+    /// Returns the challenges β, γ, α and r, and v if the openings are batched, in the
+    /// order they are squeezed while proving with transcript hash `H`. The transcript
+    /// starts with the public inputs, which the proof doesn't carry, so they are given again.
+    pub fn challenges_with_hash<H: TranscriptHash>(&self, public_inputs: &[Fr]) -> Vec<Fr> {
+        let mut transcript = Transcript::<H>::new();
+        let (beta, gamma, alpha) = transcript.replay_commitments(
+            public_inputs,
+            &self.metadata,
+            &self.t_comm,
+            &self.z_comm,
+            [&self.q_gate_comm, &self.q_input_comm, &self.q_perm_comm],
+        );
+        let r = transcript.squeeze_challenge();
+
+        let mut challenges = vec![beta, gamma, alpha, r];
+        if let Openings::Batched(_) = self.openings {
+            for value in self.evaluations.values() {
+                transcript.append_scalar(&value);
+            }
+            challenges.push(transcript.squeeze_challenge());
+        }
+        challenges
+    }
+
+    /// Returns commitments to T, Z and the quotients of gate, input and permutation constraints.
+    pub fn commitments(&self) -> [G1Affine; 5] {
+        [
+            &self.t_comm,
+            &self.z_comm,
+            &self.q_gate_comm,
+            &self.q_input_comm,
+            &self.q_perm_comm,
+        ]
+        .map(S::commitment_to_affine)
+    }
+
+    /// Returns T(r), T(𝜔r), T(𝜔^2r), S(r), W(r), Z(r), Z(𝜔r) and the quotients at r.
+    pub fn evaluations(&self) -> [Fr; 10] {
+        self.evaluations.values()
+    }
+
+    /// Returns all group elements of the proof.
+    /// Commitments come first, followed by the opening proofs in the order of evaluations
+    /// (or of the points r, 𝜔r and 𝜔^2r if batched), the opening of the output claim
+    /// and the openings of revealed cells.
+    pub fn group_elements(&self) -> Vec<G1Affine> {
+        let openings = self
+            .openings()
+            .flat_map(|opening| S::opening_elements(opening).0);
+        self.commitments().into_iter().chain(openings).collect()
+    }
+
+    /// Returns all field elements of the proof.
+    /// Evaluations come first, followed by the scalars of the opening proofs,
+    /// the claimed output and the values of revealed cells.
+    pub fn field_elements(&self) -> Vec<Fr> {
+        let openings = self
+            .openings()
+            .flat_map(|opening| S::opening_elements(opening).1);
+        self.evaluations()
+            .into_iter()
+            .chain(openings)
+            .chain(self.claimed_output())
+            .chain(self.revealed_cells.iter().map(|revealed| revealed.value))
+            .collect()
+    }
+
+    /// Serialize the proof as the format version byte followed by its compressed encoding.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![PROOF_VERSION];
+        self.serialize_compressed(&mut bytes)
+            .expect("Serializing into vec should not fail");
+        bytes
+    }
+
+    /// Deserialize a proof written by `to_bytes`.
+    /// Returns error if the proof was written in an unknown format version.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::from_reader(bytes)
+    }
+
+    /// Deserialize a proof written by `to_bytes` from `reader` as it is read,
+    /// without buffering the whole proof.
+    pub fn from_reader(mut reader: impl Read) -> Result<Self> {
+        let mut version = [0u8];
+        reader
+            .read_exact(&mut version)
+            .map_err(|_| anyhow!("Proof should not be empty."))?;
+        if version[0] != PROOF_VERSION {
+            return Err(anyhow!("unsupported proof version {}", version[0]));
+        }
+
+        Ok(Self::deserialize_compressed(reader)?)
+    }
+
+    fn openings(&self) -> impl Iterator<Item = &S::Opening> {
+        let openings = match &self.openings {
+            Openings::Separate(o) => vec![
+                &o.t,
+                &o.t_omega,
+                &o.t_omega2,
+                &o.s,
+                &o.sigma,
+                &o.z,
+                &o.z_omega,
+                &o.q_gate,
+                &o.q_input,
+                &o.q_perm,
+            ],
+            Openings::Batched(o) => vec![&o.r, &o.omega_r, &o.omega2_r],
+        };
+        openings
+            .into_iter()
+            .chain(self.output_claim.as_ref().map(|claim| &claim.opening))
+            .chain(self.revealed_cells.iter().map(|revealed| &revealed.opening))
+    }
+}
+
+/// Proofs of the same circuit and public inputs with different witnesses,
+/// produced by `Prover::prove_many`.
+/// The proofs are not aggregated: each one is verified on its own.
+#[derive(Clone, Debug)]
+pub struct ProofBatch<S: CommitmentScheme = Kzg> {
+    pub(crate) proofs: Vec<Proof<S>>,
+}
+
+impl<S: CommitmentScheme> ProofBatch<S> {
+    /// Returns the proof of each witness in order.
+    pub fn proofs(&self) -> &[Proof<S>] {
+        &self.proofs
+    }
+}
+
+/// Proof with the values of cells computed while proving, produced by
+/// `Prover::prove_with_public_outputs`. Each value is revealed with an opening of T
+/// at its cell, so the verifier learns the outputs without giving them at setup.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ProofWithPublicOutputs<S: CommitmentScheme = Kzg> {
+    pub(crate) proof: Proof<S>,
+    pub(crate) public_outputs: Vec<Fr>,
+}
+
+impl<S: CommitmentScheme> ProofWithPublicOutputs<S> {
+    /// Returns the proof carrying the openings of the output cells.
+    pub fn proof(&self) -> &Proof<S> {
+        &self.proof
+    }
+
+    /// Returns the computed value of each output cell in the order they were requested.
+    pub fn public_outputs(&self) -> &[Fr] {
+        &self.public_outputs
+    }
+}
+
+/// Work done by verifying a proof, reported by `Verifier::verification_cost`.
+///
+/// Field operations count multiplications and inversions, with a power taken by square
+/// and multiply. Additions and the hashing of the transcript are not counted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VerificationCost {
+    /// Number of pairings.
+    pub pairings: usize,
+    /// Number of multi-scalar multiplications in G1, including single scalar multiplications.
+    pub msms: usize,
+    /// Total number of scalar and point pairs of the multi-scalar multiplications.
+    pub msm_terms: usize,
+    /// Number of field multiplications and inversions.
+    pub field_ops: usize,
+}
+
+/// Proof whose challenge point r is sampled from the quadratic extension `Fr2`.
+/// Each evaluation at an extension point is reduced to openings at a base field point.
+#[derive(Clone, Debug)]
+pub struct ExtensionProof<S: CommitmentScheme = Kzg> {
+    pub(crate) domain_size: usize,
+    pub(crate) t_comm: S::Commitment,
+    pub(crate) z_comm: S::Commitment,
+    pub(crate) q_gate_comm: S::Commitment,
+    pub(crate) q_input_comm: S::Commitment,
+    pub(crate) q_perm_comm: S::Commitment,
+
+    pub(crate) evaluations: ProofEvaluations<Fr2>,
+    /// Openings for each of `evaluations` in the same order.
+    pub(crate) openings: Vec<ExtensionOpening<S>>,
+
+    pub(crate) metadata: Vec<u8>,
+}
+
+/// Opening of P at an extension point x.
+/// P = Q⋅m + s0 + s1⋅X where m is the minimal polynomial of x, so P(x) = s0 + s1⋅x.
+/// P and Q are opened at a base field point z sampled after Q is committed.
+#[derive(Clone, Debug)]
+pub(crate) struct ExtensionOpening<S: CommitmentScheme> {
+    /// Commitment to the quotient Q.
+    pub(crate) q_comm: S::Commitment,
+    /// P(z)
+    pub(crate) p_value: Fr,
+    /// Q(z)
+    pub(crate) q_value: Fr,
+    pub(crate) p_opening: S::Opening,
+    pub(crate) q_opening: S::Opening,
+}
+
+/// Claimed output value T(𝜔^output) and its opening proof.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub(crate) struct OutputClaim<S: CommitmentScheme> {
+    pub(crate) value: Fr,
+    pub(crate) opening: S::Opening,
+}
+
+/// Value of a cell T(𝜔^cell) revealed to the verifier and its opening proof.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub(crate) struct RevealedCell<S: CommitmentScheme> {
+    pub(crate) cell: usize,
+    pub(crate) value: Fr,
+    pub(crate) opening: S::Opening,
+}
+
+/// Evaluations of the polynomials at the challenge point r.
+#[derive(Clone, Copy, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub(crate) struct ProofEvaluations<E: CanonicalSerialize + CanonicalDeserialize = Fr> {
+    /// T(r)
+    pub(crate) t: E,
+    /// T(𝜔r)
+    pub(crate) t_omega: E,
+    /// T(𝜔^2r)
+    pub(crate) t_omega2: E,
+    /// S(r)
+    pub(crate) s: E,
+    /// W(r)
+    pub(crate) sigma: E,
+    /// Z(r)
+    pub(crate) z: E,
+    /// Z(𝜔r)
+    pub(crate) z_omega: E,
+    pub(crate) q_gate: E,
+    pub(crate) q_input: E,
+    pub(crate) q_perm: E,
+}
+
+impl<E: Copy + CanonicalSerialize + CanonicalDeserialize> ProofEvaluations<E> {
+    /// Returns the evaluations in the order of the fields.
+    pub(crate) fn values(&self) -> [E; 10] {
+        [
+            self.t,
+            self.t_omega,
+            self.t_omega2,
+            self.s,
+            self.sigma,
+            self.z,
+            self.z_omega,
+            self.q_gate,
+            self.q_input,
+            self.q_perm,
+        ]
+    }
+}
+
+/// Opening proofs for each of `ProofEvaluations`.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub(crate) struct ProofOpenings<S: CommitmentScheme> {
+    pub(crate) t: S::Opening,
+    pub(crate) t_omega: S::Opening,
+    pub(crate) t_omega2: S::Opening,
+    pub(crate) s: S::Opening,
+    pub(crate) sigma: S::Opening,
+    pub(crate) z: S::Opening,
+    pub(crate) z_omega: S::Opening,
+    pub(crate) q_gate: S::Opening,
+    pub(crate) q_input: S::Opening,
+    pub(crate) q_perm: S::Opening,
+}
+
+/// Opening proofs of a proof, one per evaluation or one per point if batched.
+#[derive(Clone, Debug)]
+pub(crate) enum Openings<S: CommitmentScheme> {
+    Separate(ProofOpenings<S>),
+    Batched(BatchedOpenings<S>),
+}
+
+/// Opening proofs of the polynomials evaluated at each point, combined with powers of
+/// a challenge v squeezed after the evaluations. See `ProverConfig::batch_openings`.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub(crate) struct BatchedOpenings<S: CommitmentScheme> {
+    /// T, S, W, Z and the quotients at r.
+    pub(crate) r: S::Opening,
+    /// T and Z at 𝜔r.
+    pub(crate) omega_r: S::Opening,
+    /// T at 𝜔^2r.
+    pub(crate) omega2_r: S::Opening,
+}
+
+/// Openings are written as a tag byte, 0 if separate and 1 if batched, and the openings.
+impl<S: CommitmentScheme> CanonicalSerialize for Openings<S> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        match self {
+            Self::Separate(openings) => {
+                0u8.serialize_with_mode(&mut writer, compress)?;
+                openings.serialize_with_mode(writer, compress)
+            }
+            Self::Batched(openings) => {
+                1u8.serialize_with_mode(&mut writer, compress)?;
+                openings.serialize_with_mode(writer, compress)
+            }
+        }
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        1 + match self {
+            Self::Separate(openings) => openings.serialized_size(compress),
+            Self::Batched(openings) => openings.serialized_size(compress),
+        }
+    }
+}
+
+impl<S: CommitmentScheme> Valid for Openings<S> {
+    fn check(&self) -> Result<(), SerializationError> {
+        match self {
+            Self::Separate(openings) => openings.check(),
+            Self::Batched(openings) => openings.check(),
+        }
+    }
+}
+
+impl<S: CommitmentScheme> CanonicalDeserialize for Openings<S> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        match u8::deserialize_with_mode(&mut reader, compress, validate)? {
+            0 => Ok(Self::Separate(ProofOpenings::deserialize_with_mode(
+                reader, compress, validate,
+            )?)),
+            1 => Ok(Self::Batched(BatchedOpenings::deserialize_with_mode(
+                reader, compress, validate,
+            )?)),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+
+/// Polynomials computed by the prover in coefficient form, for debugging.
+#[derive(Clone, Debug)]
+pub struct ProverPolynomials<F: FftField> {
+    /// Trace polynomial T.
+    pub trace: DensePolynomial<F>,
+    /// Selector polynomial S.
+    pub selector: DensePolynomial<F>,
+    /// Public input polynomial v.
+    pub public_input: DensePolynomial<F>,
+    /// Gate constraint S⋅[T(X) + T(𝜔X)] + (1 – S)⋅T(X)⋅T(𝜔X) − T(𝜔^2X).
+    pub gate: DensePolynomial<F>,
+    /// Vanishing polynomial of the gate points 𝜔^3l.
+    pub gate_vanishing: DensePolynomial<F>,
+    /// Quotient of the gate constraint.
+    pub q_gate: DensePolynomial<F>,
+    /// Input constraint T - v.
+    pub input: DensePolynomial<F>,
+    /// Vanishing polynomial of the public input and constant cells.
+    pub input_vanishing: DensePolynomial<F>,
+    /// Quotient of the input constraint.
+    pub q_input: DensePolynomial<F>,
+}
+
+/// Verification key of a circuit.
+/// Verifier only needs this to verify a proof, not the whole circuit.
+#[derive(Clone, Debug)]
+pub struct VerificationKey<S: CommitmentScheme = Kzg> {
+    /// Size of the evaluation domain.
+    pub(crate) domain_size: usize,
+    /// Total number of cells in a circuit.
+    pub(crate) n_cells: usize,
+    /// Total number of rows in a circuit.
+    pub(crate) n_rows: usize,
+    /// Id of the first cell of each gate.
+    pub(crate) gate_ids: Vec<usize>,
+    /// Number of public inputs.
+    pub(crate) n_pub: usize,
+    /// Id of the output cell.
+    pub(crate) output_id: usize,
+    /// Id and value of each constant cell.
+    pub(crate) constants: Vec<(usize, Fr)>,
+    /// Commitment to the selector polynomial S.
+    pub(crate) s_comm: S::Commitment,
+    /// Commitment to the wire rotation polynomial W.
+    pub(crate) sigma_comm: S::Commitment,
+    /// Parameters of the commitment scheme used to check openings.
+    pub(crate) pcs_vk: S::VerifierParams,
+}
+
+impl VerificationKey<Kzg> {
+    /// Export the curve points of the key as Solidity constants for an EVM verifier.
+    /// Points are on BLS12-381, not BN254, so each coordinate is padded to 64 bytes
+    /// as the EIP-2537 precompiles expect. A G2 coordinate is split into c0 and c1.
+    pub fn to_solidity_constants(&self) -> String {
+        let mut constants = vec![];
+        let mut push = |name: &str, coords: &[(&str, Fq)]| {
+            for (coord, value) in coords {
+                constants.push(format!(
+                    "bytes constant {}_{} = hex\"{}\";",
+                    name,
+                    coord,
+                    encode_fq(value)
+                ));
+            }
+        };
+        let g1 = |p: &G1Affine| [("X", p.x), ("Y", p.y)];
+        let g2 = |p: &G2Affine| {
+            [
+                ("X_C0", p.x.c0),
+                ("X_C1", p.x.c1),
+                ("Y_C0", p.y.c0),
+                ("Y_C1", p.y.c1),
+            ]
+        };
+
+        push("S_COMM", &g1(&self.s_comm.0));
+        push("SIGMA_COMM", &g1(&self.sigma_comm.0));
+        push("G", &g1(&self.pcs_vk.g));
+        push("GAMMA_G", &g1(&self.pcs_vk.gamma_g));
+        push("H", &g2(&self.pcs_vk.h));
+        push("BETA_H", &g2(&self.pcs_vk.beta_h));
+
+        constants.join("\n")
+    }
+}
+
+/// Big-endian hex of `x` left-padded to 64 bytes, the field element encoding of EIP-2537.
+/// The point at infinity has zero coordinates, which is also its encoding.
+fn encode_fq(x: &Fq) -> String {
+    let mut bytes = vec![0u8; 16];
+    bytes.extend(x.into_bigint().to_bytes_be());
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Proving key of a circuit.
+/// Prover only needs the parameters to commit and open polynomials.
 #[derive(Clone)]
-pub struct PublicParameters {
-    pub(crate) kzg_params: UniversalParams<Bls12_381>,
+pub struct ProverKey<S: CommitmentScheme = Kzg> {
+    /// Parameters of the commitment scheme used to commit and open polynomials.
+    pub(crate) params: S::Params,
+    /// Hash of the public inputs given at setup.
+    pub(crate) public_inputs_digest: Fr,
+}
+
+/// Public parameters produced at setup, made of the keys of prover and verifier.
+#[derive(Clone)]
+pub struct PublicParameters<S: CommitmentScheme = Kzg> {
+    pub(crate) pk: ProverKey<S>,
+    pub(crate) vk: VerificationKey<S>,
+}
+
+impl<S: CommitmentScheme> PublicParameters<S> {
+    /// Returns proving key of the circuit.
+    pub fn prover_key(&self) -> &ProverKey<S> {
+        &self.pk
+    }
+
+    /// Returns verification key of the circuit.
+    pub fn verification_key(&self) -> &VerificationKey<S> {
+        &self.vk
+    }
+
+    /// Split into the proving key and the verification key,
+    /// so that each party only holds its own key.
+    pub fn into_keys(self) -> (ProverKey<S>, VerificationKey<S>) {
+        (self.pk, self.vk)
+    }
+
+    /// Returns true if the SRS is large enough to prove `circ`.
+    /// Parameters can be shared by any such circuit with `for_circuit`.
+    pub fn supports_circuit(&self, circ: &Circuit) -> bool {
+        Self::required_degree(circ) <= S::max_degree(&self.pk.params)
+    }
+
+    /// Maximum degree of the polynomials committed while proving `circ`.
+    pub(crate) fn required_degree(circ: &Circuit) -> usize {
+        circ.degree_bound()
+    }
 }
 
 pub(crate) type UniPoly381 = DensePolynomial<<Bls12_381 as Pairing>::ScalarField>;
@@ -1,24 +1,576 @@
-use ark_ff::FftField;
+use anyhow::{anyhow, Result};
+use ark_bls12_381::Fr;
+use ark_ff::{FftField, Field, One, Zero};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain, Polynomial};
+use std::marker::PhantomData;
 
 use crate::{
-    circuit::Circuit,
-    types::{Proof, PublicParameters},
+    circuit::{CellId, Circuit, InputIndex},
+    extension::{minimal_polynomial, remainder_from_value, Fr2},
+    pcs::{CommitmentScheme, Kzg},
+    transcript::{Poseidon, Transcript, TranscriptEntry, TranscriptHash},
+    types::{
+        ExtensionProof, Openings, Proof, ProofBatch, ProofEvaluations, ProofWithPublicOutputs,
+        PublicParameters, VerificationCost, VerificationKey,
+    },
 };
 
-pub struct Verifier<F: FftField> {
-    circuit: Circuit,
+pub struct Verifier<F: FftField, S: CommitmentScheme = Kzg, H: TranscriptHash = Poseidon> {
+    circuit: Option<Circuit>,
+    vk: VerificationKey<S>,
     public_inputs: Vec<F>,
+    /// Hash deriving the Fiat-Shamir challenges, which has to match the prover's.
+    hash: PhantomData<H>,
 }
 
-impl<F: FftField> Verifier<F> {
-    pub fn new(circuit: Circuit, pp: PublicParameters, public_inputs: Vec<F>) -> Self {
+/// Builder of a verifier naming each argument, created by `Verifier::builder`.
+/// Circuit, public parameters and public inputs are required.
+pub struct VerifierBuilder<F: FftField, S: CommitmentScheme = Kzg, H: TranscriptHash = Poseidon> {
+    circuit: Option<Circuit>,
+    pp: Option<PublicParameters<S>>,
+    public: Option<Vec<F>>,
+    hash: PhantomData<H>,
+}
+
+impl<F: FftField, S: CommitmentScheme, H: TranscriptHash> VerifierBuilder<F, S, H> {
+    pub fn circuit(mut self, circuit: Circuit) -> Self {
+        self.circuit = Some(circuit);
+        self
+    }
+
+    pub fn params(mut self, pp: PublicParameters<S>) -> Self {
+        self.pp = Some(pp);
+        self
+    }
+
+    /// Set the values of the public inputs in order.
+    pub fn public(mut self, public: impl Into<Vec<F>>) -> Self {
+        self.public = Some(public.into());
+        self
+    }
+
+    /// Returns error if a required argument is not set
+    /// or the number of public inputs doesn't match the circuit.
+    pub fn build(self) -> Result<Verifier<F, S, H>> {
+        let circuit = self.circuit.ok_or(anyhow!("Circuit is not set."))?;
+        let pp = self.pp.ok_or(anyhow!("Public parameters are not set."))?;
+        let public = self.public.ok_or(anyhow!("Public inputs are not set."))?;
+
+        let n_pub = circuit.input_config.n_pub();
+        if public.len() != n_pub {
+            return Err(anyhow!(
+                "Expected {} public inputs, got {}.",
+                n_pub,
+                public.len()
+            ));
+        }
+        Ok(Verifier::new(circuit, pp, public))
+    }
+}
+
+impl<F: FftField, S: CommitmentScheme, H: TranscriptHash> Verifier<F, S, H> {
+    /// Start building a verifier with named arguments instead of positional ones.
+    pub fn builder() -> VerifierBuilder<F, S, H> {
+        VerifierBuilder {
+            circuit: None,
+            pp: None,
+            public: None,
+            hash: PhantomData,
+        }
+    }
+
+    pub fn new(
+        circuit: Circuit,
+        pp: PublicParameters<S>,
+        public_inputs: impl Into<Vec<F>>,
+    ) -> Self {
+        Self {
+            circuit: Some(circuit),
+            vk: pp.vk,
+            public_inputs: public_inputs.into(),
+            hash: PhantomData,
+        }
+    }
+
+    /// Create verifier only from verification key without the original circuit.
+    pub fn from_vk(vk: VerificationKey<S>, public_inputs: impl Into<Vec<F>>) -> Self {
         Self {
-            circuit,
-            public_inputs,
+            circuit: None,
+            vk,
+            public_inputs: public_inputs.into(),
+            hash: PhantomData,
+        }
+    }
+
+    /// Returns the public inputs the proof is verified against.
+    pub fn public_inputs(&self) -> &[F] {
+        &self.public_inputs
+    }
+
+    /// Returns the circuit being verified.
+    /// Verifier created from a verification key has no circuit.
+    pub fn circuit(&self) -> Option<&Circuit> {
+        self.circuit.as_ref()
+    }
+}
+
+impl<S: CommitmentScheme, H: TranscriptHash> Verifier<Fr, S, H> {
+    pub fn verify(&mut self, proof: Proof<S>) -> bool {
+        self.check(&proof).unwrap_or(false)
+    }
+
+    /// Verify a proof written by `Proof::to_bytes`, deserializing it from `reader` as it
+    /// is read, e.g. from a network stream.
+    /// Returns error if the proof can't be deserialized.
+    pub fn verify_reader(&mut self, reader: impl std::io::Read) -> Result<bool> {
+        let proof = Proof::from_reader(reader)?;
+        Ok(self.verify(proof))
+    }
+
+    /// Verify like `verify`, but run every opening and identity check even after one fails
+    /// and combine the results at the end, so the time taken doesn't tell which one failed.
+    /// Pairings and field arithmetic of arkworks are not constant-time themselves, and a
+    /// malformed proof (e.g. of a wrong domain size) is still rejected right away.
+    pub fn verify_constant_time(&mut self, proof: Proof<S>) -> bool {
+        self.check_with_transcript(&proof, &mut Transcript::new(), true)
+            .unwrap_or(false)
+    }
+
+    /// Returns the work `verify` does for `proof`, e.g. to budget the gas of an on-chain
+    /// verifier. It depends on the size of the circuit, the commitment scheme, and whether
+    /// the proof batches its openings or claims the output.
+    pub fn verification_cost(&self, proof: &Proof<S>) -> VerificationCost {
+        let vk = &self.vk;
+        let lagrange = |id: usize| pow_cost(id) + 4;
+
+        // Z_H(r), 𝜔r and 𝜔^2r
+        let mut field_ops = pow_cost(vk.domain_size) + 3;
+        // gate identity, with a factor of the vanishing polynomial per gate
+        field_ops += vk
+            .gate_ids
+            .iter()
+            .map(|id| pow_cost(*id) + 1)
+            .sum::<usize>()
+            + 4;
+        // input identity, with a Lagrange basis and a factor of the vanishing polynomial per input
+        field_ops += (0..vk.n_pub)
+            .map(|i| InputIndex(i).cell(vk.n_cells).0)
+            .chain(vk.constants.iter().map(|(id, _)| *id))
+            .map(|id| lagrange(id) + pow_cost(id) + 2)
+            .sum::<usize>()
+            + 1;
+        // permutation identity
+        field_ops += lagrange(0) + 7;
+
+        let (mut checks, msms, msm_terms) = match &proof.openings {
+            Openings::Separate(_) => (10, 0, 0),
+            Openings::Batched(_) => {
+                // 7 commitments at r and 2 at 𝜔r are combined
+                field_ops += 18;
+                (3, 2, 9)
+            }
+        };
+        if proof.output_claim.is_some() {
+            checks += 1;
+            field_ops += pow_cost(vk.output_id);
+        }
+
+        let check = S::check_cost(&vk.pcs_vk);
+        VerificationCost {
+            pairings: checks * check.pairings,
+            msms: msms + checks * check.msms,
+            msm_terms: msm_terms + checks * check.msm_terms,
+            field_ops: field_ops + checks * check.field_ops,
+        }
+    }
+
+    /// Verify a proof whose evaluation point lies in the extension `Fr2`.
+    pub fn verify_extension(&mut self, proof: ExtensionProof<S>) -> bool {
+        self.check_extension(&proof).unwrap_or(false)
+    }
+
+    /// Check the value of `cell` revealed in `proof` equals `expected`.
+    /// The revealed opening is checked against the commitment to T at the point of the cell,
+    /// so this only spot-checks the wire. The proof itself is checked by `verify`.
+    /// Cells out of the circuit are rejected, since their points wrap around the domain.
+    pub fn check_public_opening(&self, proof: &Proof<S>, cell: CellId, expected: Fr) -> bool {
+        let CellId(cell) = cell;
+        if cell >= self.vk.n_cells {
+            return false;
+        }
+        let Ok(domain) = self.check_domain(proof.domain_size) else {
+            return false;
+        };
+
+        proof
+            .revealed_cells
+            .iter()
+            .find(|revealed| revealed.cell == cell)
+            .is_some_and(|revealed| {
+                revealed.value == expected
+                    && S::check(
+                        &self.vk.pcs_vk,
+                        &proof.t_comm,
+                        domain.element(cell),
+                        expected,
+                        &revealed.opening,
+                    )
+                    .unwrap_or(false)
+            })
+    }
+
+    /// Verify `proof` and its public outputs, which have to be the values of `cells`.
+    /// The cells are agreed on with the prover like the circuit, and each output is
+    /// checked against the opening of T at its cell.
+    pub fn verify_with_public_outputs(
+        &mut self,
+        proof: ProofWithPublicOutputs<S>,
+        cells: &[CellId],
+    ) -> bool {
+        proof.public_outputs.len() == cells.len()
+            && cells
+                .iter()
+                .zip(&proof.public_outputs)
+                .all(|(cell, output)| self.check_public_opening(&proof.proof, *cell, *output))
+            && self.verify(proof.proof)
+    }
+
+    /// Verify all the proofs in `batch` against the public inputs of the verifier,
+    /// one after another, so it costs as much as verifying each of them.
+    /// An empty batch is rejected.
+    pub fn verify_batch(&mut self, batch: ProofBatch<S>) -> bool {
+        !batch.proofs.is_empty()
+            && batch
+                .proofs
+                .iter()
+                .all(|proof| self.check(proof).unwrap_or(false))
+    }
+
+    /// Verify a proof, recording every value absorbed into and squeezed out of
+    /// the transcript to compare with `Prover::prove_with_transcript_log`.
+    pub fn verify_with_transcript_log(&mut self, proof: Proof<S>) -> (bool, Vec<TranscriptEntry>) {
+        let mut transcript = Transcript::with_log();
+        let valid = self
+            .check_with_transcript(&proof, &mut transcript, false)
+            .unwrap_or(false);
+        (valid, transcript.log().to_vec())
+    }
+
+    pub(crate) fn check(&self, proof: &Proof<S>) -> Result<bool> {
+        self.check_with_transcript(proof, &mut Transcript::new(), false)
+    }
+
+    /// Returns false on the first failed check unless `constant_time` is set.
+    fn check_with_transcript(
+        &self,
+        proof: &Proof<S>,
+        transcript: &mut Transcript<H>,
+        constant_time: bool,
+    ) -> Result<bool> {
+        let vk = &self.vk;
+        let domain = self.check_domain(proof.domain_size)?;
+        let omega = domain.group_gen();
+
+        // recompute challenges in the same order as the prover
+        let (beta, gamma, alpha) = self.replay_transcript(
+            transcript,
+            &proof.metadata,
+            &proof.t_comm,
+            &proof.z_comm,
+            [&proof.q_gate_comm, &proof.q_input_comm, &proof.q_perm_comm],
+        );
+        let r = transcript.squeeze_challenge();
+
+        // check opening proofs of all the evaluations
+        let evals = &proof.evaluations;
+        let checks = match &proof.openings {
+            Openings::Separate(openings) => vec![
+                (proof.t_comm, r, evals.t, &openings.t),
+                (proof.t_comm, omega * r, evals.t_omega, &openings.t_omega),
+                (
+                    proof.t_comm,
+                    omega * omega * r,
+                    evals.t_omega2,
+                    &openings.t_omega2,
+                ),
+                (vk.s_comm, r, evals.s, &openings.s),
+                (vk.sigma_comm, r, evals.sigma, &openings.sigma),
+                (proof.z_comm, r, evals.z, &openings.z),
+                (proof.z_comm, omega * r, evals.z_omega, &openings.z_omega),
+                (proof.q_gate_comm, r, evals.q_gate, &openings.q_gate),
+                (proof.q_input_comm, r, evals.q_input, &openings.q_input),
+                (proof.q_perm_comm, r, evals.q_perm, &openings.q_perm),
+            ],
+            Openings::Batched(openings) => {
+                for value in evals.values() {
+                    transcript.append_scalar(&value);
+                }
+                let v = transcript.squeeze_challenge();
+                let (comm_r, value_r) = combine::<S>(
+                    &[
+                        (&proof.t_comm, evals.t),
+                        (&vk.s_comm, evals.s),
+                        (&vk.sigma_comm, evals.sigma),
+                        (&proof.z_comm, evals.z),
+                        (&proof.q_gate_comm, evals.q_gate),
+                        (&proof.q_input_comm, evals.q_input),
+                        (&proof.q_perm_comm, evals.q_perm),
+                    ],
+                    v,
+                );
+                let (comm_omega_r, value_omega_r) = combine::<S>(
+                    &[
+                        (&proof.t_comm, evals.t_omega),
+                        (&proof.z_comm, evals.z_omega),
+                    ],
+                    v,
+                );
+                vec![
+                    (comm_r, r, value_r, &openings.r),
+                    (comm_omega_r, omega * r, value_omega_r, &openings.omega_r),
+                    (
+                        proof.t_comm,
+                        omega * omega * r,
+                        evals.t_omega2,
+                        &openings.omega2_r,
+                    ),
+                ]
+            }
+        };
+        let mut valid = true;
+        for (comm, point, value, opening) in checks {
+            valid &= S::check(&vk.pcs_vk, &comm, point, value, opening)?;
+            if !valid && !constant_time {
+                return Ok(false);
+            }
+        }
+
+        valid &= self.check_identities(evals, r, omega, beta, gamma, alpha)?;
+        if !valid && !constant_time {
+            return Ok(false);
         }
+
+        // 4. output
+        // T(𝜔^output) equals to the claimed output if the proof has a claim.
+        if let Some(claim) = &proof.output_claim {
+            let point = domain.element(vk.output_id);
+            valid &= S::check(
+                &vk.pcs_vk,
+                &proof.t_comm,
+                point,
+                claim.value,
+                &claim.opening,
+            )?;
+        }
+
+        Ok(valid)
     }
 
-    pub fn verify(&mut self, proof: Proof) -> bool {
-        todo!()
+    pub(crate) fn check_extension(&self, proof: &ExtensionProof<S>) -> Result<bool> {
+        let vk = &self.vk;
+        let domain = self.check_domain(proof.domain_size)?;
+        let omega = domain.group_gen();
+
+        let mut transcript = Transcript::<H>::new();
+        let (beta, gamma, alpha) = self.replay_transcript(
+            &mut transcript,
+            &proof.metadata,
+            &proof.t_comm,
+            &proof.z_comm,
+            [&proof.q_gate_comm, &proof.q_input_comm, &proof.q_perm_comm],
+        );
+        let r = transcript.squeeze_extension_challenge();
+        if proof.openings.len() != 10 {
+            return Ok(false);
+        }
+
+        let evals = &proof.evaluations;
+        let omega_ext = Fr2::from_base_prime_field(omega);
+        let polys = [
+            (&proof.t_comm, r),
+            (&proof.t_comm, omega_ext * r),
+            (&proof.t_comm, omega_ext * omega_ext * r),
+            (&vk.s_comm, r),
+            (&vk.sigma_comm, r),
+            (&proof.z_comm, r),
+            (&proof.z_comm, omega_ext * r),
+            (&proof.q_gate_comm, r),
+            (&proof.q_input_comm, r),
+            (&proof.q_perm_comm, r),
+        ];
+        let values = evals.values();
+        values
+            .iter()
+            .for_each(|value| transcript.append_extension_scalar(value));
+        proof
+            .openings
+            .iter()
+            .for_each(|opening| transcript.append_commitment(&opening.q_comm));
+        let z = transcript.squeeze_challenge();
+
+        // P(z) - s0 - s1⋅z = Q(z)⋅m(z), where P(x) = s0 + s1⋅x
+        for (((comm, point), value), opening) in polys.iter().zip(values).zip(&proof.openings) {
+            let Some((s0, s1)) = remainder_from_value(*point, value) else {
+                return Ok(false);
+            };
+            let m = minimal_polynomial(*point).evaluate(&z);
+            if opening.p_value - s0 - s1 * z != opening.q_value * m {
+                return Ok(false);
+            }
+            if !S::check(&vk.pcs_vk, comm, z, opening.p_value, &opening.p_opening)?
+                || !S::check(
+                    &vk.pcs_vk,
+                    &opening.q_comm,
+                    z,
+                    opening.q_value,
+                    &opening.q_opening,
+                )?
+            {
+                return Ok(false);
+            }
+        }
+
+        self.check_identities(evals, r, omega, beta, gamma, alpha)
     }
+
+    /// Check the proof is for this circuit and returns its evaluation domain.
+    fn check_domain(&self, proof_domain_size: usize) -> Result<GeneralEvaluationDomain<Fr>> {
+        let vk = &self.vk;
+
+        // builder rejects circuits without gates, a key or circuit without them is malformed
+        if vk.gate_ids.is_empty() || self.circuit.as_ref().is_some_and(|c| c.num_gates() == 0) {
+            return Err(anyhow!("Circuit has no gates."));
+        }
+
+        // reject proofs for a different sized circuit before any opening checks
+        let domain_size = match &self.circuit {
+            Some(circ) => circ.n_cells().checked_next_power_of_two().unwrap(),
+            None => vk.domain_size,
+        };
+        if proof_domain_size != domain_size {
+            return Err(anyhow!(
+                "Proof domain size {} does not match circuit domain size {}.",
+                proof_domain_size,
+                domain_size
+            ));
+        }
+
+        // verification key should describe the circuit if the verifier knows it.
+        if let Some(circ) = &self.circuit {
+            if circ.n_cells() != vk.n_cells || circ.n_rows() != vk.n_rows {
+                return Err(anyhow!("Verification key does not match the circuit."));
+            }
+        }
+        if self.public_inputs.len() != vk.n_pub {
+            return Err(anyhow!(
+                "Expected {} public inputs, got {}.",
+                vk.n_pub,
+                self.public_inputs.len()
+            ));
+        }
+
+        GeneralEvaluationDomain::<Fr>::new(vk.domain_size)
+            .ok_or(anyhow!("Domain cannot be constructed from circuit size"))
+    }
+
+    /// Absorb public inputs, metadata and commitments into `transcript`, returning β, γ and α.
+    fn replay_transcript(
+        &self,
+        transcript: &mut Transcript<H>,
+        metadata: &[u8],
+        t_comm: &S::Commitment,
+        z_comm: &S::Commitment,
+        quotient_comms: [&S::Commitment; 3],
+    ) -> (Fr, Fr, Fr) {
+        transcript.replay_commitments(
+            &self.public_inputs,
+            metadata,
+            t_comm,
+            z_comm,
+            quotient_comms,
+        )
+    }
+
+    /// Check gate, input and permutation constraints on the evaluations at `r`.
+    /// `r` is either in Fr or in an extension of it.
+    fn check_identities<E: Field<BasePrimeField = Fr>>(
+        &self,
+        evals: &ProofEvaluations<E>,
+        r: E,
+        omega: Fr,
+        beta: Fr,
+        gamma: Fr,
+        alpha: Fr,
+    ) -> Result<bool> {
+        let vk = &self.vk;
+        let lift = E::from_base_prime_field;
+        let n = vk.domain_size as u64;
+        let z_h = r.pow([n]) - E::one();
+
+        // L_i(r) = 𝜔^i⋅(r^N - 1) / (N⋅(r - 𝜔^i))
+        let lagrange = |id: usize| -> Result<E> {
+            let omega_i = lift(omega.pow([id as u64]));
+            let den = (lift(Fr::from(n)) * (r - omega_i))
+                .inverse()
+                .ok_or(anyhow!("Challenge should not be in the domain."))?;
+            Ok(omega_i * z_h * den)
+        };
+
+        // 1. gates
+        // S(r)⋅[T(r) + T(𝜔r)] + (1 – S(r))⋅T(r)⋅T(𝜔r) − T(𝜔2r) = q_gate(r)⋅Z_gate(r)
+        let gate_vanishing = vk
+            .gate_ids
+            .iter()
+            .map(|id| r - lift(omega.pow([*id as u64])))
+            .product::<E>();
+        let gate = evals.s * (evals.t + evals.t_omega)
+            + (E::one() - evals.s) * evals.t * evals.t_omega
+            - evals.t_omega2;
+        // every identity is evaluated for `Verifier::verify_constant_time`
+        let gate_holds = gate == evals.q_gate * gate_vanishing;
+
+        // 2. inputs
+        // T(r) - v(r) = q_input(r)⋅Z_input(r)
+        let mut v = E::zero();
+        for (i, x) in self.public_inputs.iter().enumerate() {
+            v += lift(*x) * lagrange(InputIndex(i).cell(vk.n_cells).0)?;
+        }
+        for (id, value) in &vk.constants {
+            v += lift(*value) * lagrange(*id)?;
+        }
+        let input_vanishing = (0..vk.n_pub)
+            .map(|i| InputIndex(i).cell(vk.n_cells).0)
+            .chain(vk.constants.iter().map(|(id, _)| *id))
+            .map(|id| r - lift(omega.pow([id as u64])))
+            .product::<E>();
+        let input_holds = evals.t - v == evals.q_input * input_vanishing;
+
+        // 3. wires
+        // Z(𝜔r)⋅(T(r) + βW(r) + γ) - Z(r)⋅(T(r) + βr + γ) + α⋅L0(r)⋅(Z(r) - 1) = q_perm(r)⋅Z_H(r)
+        let (beta, gamma, alpha) = (lift(beta), lift(gamma), lift(alpha));
+        let perm = evals.z_omega * (evals.t + beta * evals.sigma + gamma)
+            - evals.z * (evals.t + beta * r + gamma)
+            + alpha * lagrange(0)? * (evals.z - E::one());
+        let perm_holds = perm == evals.q_perm * z_h;
+
+        Ok(gate_holds & input_holds & perm_holds)
+    }
+}
+
+/// Returns the commitment and value of Σ v^i⋅P_i from those of each P_i.
+fn combine<S: CommitmentScheme>(terms: &[(&S::Commitment, Fr)], v: Fr) -> (S::Commitment, Fr) {
+    let mut coeff = Fr::one();
+    let mut comm_terms = vec![];
+    let mut value = Fr::zero();
+    for (comm, eval) in terms {
+        comm_terms.push((coeff, *comm));
+        value += coeff * eval;
+        coeff *= v;
+    }
+    (S::combine(&comm_terms), value)
+}
+
+/// Multiplications computing x^e by square and multiply.
+fn pow_cost(e: usize) -> usize {
+    (usize::BITS - e.leading_zeros() + e.count_ones()) as usize
 }
@@ -0,0 +1,66 @@
+use ark_bls12_381::Fr;
+use ark_std::test_rng;
+use miniplonk::{setup, Circuit, CircuitBuilder, InputAssignment, InputConfig, Prover, Verifier};
+
+/// Coefficients c_0, .., c_24 of the evaluated polynomial.
+const COEFFS: [u64; 25] = [
+    7, 1, 8, 2, 8, 1, 8, 2, 8, 4, 5, 9, 0, 4, 5, 2, 3, 5, 3, 6, 0, 2, 8, 7, 4,
+];
+
+/// Circuit proving y = Σ c_i⋅x^i for private x and public y.
+/// Horner's rule takes a multiplication and an addition per coefficient, 48 gates in total.
+fn polynomial_circuit() -> Circuit {
+    let mut builder = CircuitBuilder::new(InputConfig::new(1, 1));
+    let (_, prv_refs) = builder.get_input_refs();
+
+    let mut acc = builder.add_constant(COEFFS[COEFFS.len() - 1]);
+    for coeff in COEFFS.iter().rev().skip(1) {
+        let product = builder.add_multiplication(acc, prv_refs[0]).unwrap();
+        let coeff = builder.add_constant(*coeff);
+        acc = builder.add_addition(product, coeff).unwrap();
+    }
+    builder.assert_output_equals(0).unwrap();
+
+    builder.build().unwrap()
+}
+
+fn evaluate(x: Fr) -> Fr {
+    COEFFS
+        .iter()
+        .rev()
+        .fold(Fr::from(0), |acc, coeff| acc * x + Fr::from(*coeff))
+}
+
+#[test]
+fn test_prove_polynomial_evaluation() {
+    let circ = polynomial_circuit();
+    assert_eq!(circ.num_gates(), 48);
+    // 144 gate cells, the constants 0 to 9 shared by the coefficients and 2 inputs
+    assert_eq!(circ.n_cells(), 156);
+
+    let x = Fr::from(3);
+    let public_inputs = vec![evaluate(x)];
+    assert_eq!(
+        circ.compute_output(&public_inputs, &[x]).unwrap(),
+        public_inputs[0]
+    );
+
+    let mut rng = test_rng();
+    let pp = setup(&circ, &public_inputs, &mut rng, circ.degree_bound()).unwrap();
+    let inputs = InputAssignment::with_values(&circ, &public_inputs, &[x]).unwrap();
+    let mut prover = Prover::<Fr>::new(circ.clone(), pp.clone(), inputs).unwrap();
+    let proof = prover.prove().unwrap();
+    assert_eq!(proof.domain_size(), 256);
+
+    let mut verifier = Verifier::<Fr>::new(circ.clone(), pp.clone(), public_inputs.clone());
+    assert!(verifier.verify(proof.clone()));
+
+    let mut verifier =
+        Verifier::<Fr>::new(circ.clone(), pp.clone(), vec![evaluate(x) + Fr::from(1)]);
+    assert!(!verifier.verify(proof));
+
+    // x = 4 doesn't evaluate to the public y
+    let inputs = InputAssignment::with_values(&circ, &public_inputs, &[Fr::from(4)]).unwrap();
+    let mut prover = Prover::<Fr>::new(circ, pp, inputs).unwrap();
+    assert!(prover.prove().is_err());
+}